@@ -83,6 +83,110 @@ pub mod kuksa {
                 }
             }
 
+            /// Splits an array literal into its elements.
+            ///
+            /// Accepts JSON-style arrays (`["a", "b"]`, `[1, 2]`) and plain comma
+            /// separated lists (`a, b`). Elements may be quoted with `"` or `'`,
+            /// in which case `\` escapes the next character and commas are kept.
+            /// Empty elements (`a,,b`, `, a`) are rejected, an empty string has to
+            /// be quoted (`""`).
+            pub fn parse_array(input: &str) -> Result<Vec<String>, ParsingError> {
+                let trimmed = input.trim();
+                let inner = match (trimmed.strip_prefix('['), trimmed.strip_suffix(']')) {
+                    (Some(_), Some(_)) => &trimmed[1..trimmed.len() - 1],
+                    (None, None) => trimmed,
+                    _ => {
+                        return Err(ParsingError::new(format!(
+                            "unbalanced brackets in '{input}'"
+                        )))
+                    }
+                };
+
+                let mut values = Vec::new();
+                let mut chars = inner.chars().peekable();
+
+                loop {
+                    while chars.next_if(|c| c.is_whitespace()).is_some() {}
+                    let Some(&first) = chars.peek() else {
+                        break;
+                    };
+
+                    let mut value = String::new();
+                    if first == '"' || first == '\'' {
+                        chars.next();
+                        let mut closed = false;
+                        while let Some(c) = chars.next() {
+                            match c {
+                                '\\' => match chars.next() {
+                                    Some(escaped) => value.push(escaped),
+                                    None => break,
+                                },
+                                c if c == first => {
+                                    closed = true;
+                                    break;
+                                }
+                                c => value.push(c),
+                            }
+                        }
+                        if !closed {
+                            return Err(ParsingError::new(format!(
+                                "unterminated string in '{input}'"
+                            )));
+                        }
+                        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+                        if chars.peek().is_some_and(|c| *c != ',') {
+                            return Err(ParsingError::new(format!(
+                                "expected ',' after string in '{input}'"
+                            )));
+                        }
+                    } else {
+                        while let Some(c) = chars.next_if(|c| *c != ',') {
+                            value.push(c);
+                        }
+                        value = value.trim_end().to_string();
+                        if value.is_empty() {
+                            return Err(ParsingError::new(format!(
+                                "empty element in '{input}'"
+                            )));
+                        }
+                    }
+
+                    values.push(value);
+
+                    if chars.next().is_none() {
+                        break;
+                    }
+                    if chars.peek().is_none() {
+                        return Err(ParsingError::new(format!("trailing ',' in '{input}'")));
+                    }
+                }
+
+                Ok(values)
+            }
+
+            fn parse_elements<T: FromStr>(value: &str) -> Result<Vec<T>, ParsingError>
+            where
+                T::Err: Display,
+            {
+                parse_array(value)?
+                    .iter()
+                    .map(|element| {
+                        element
+                            .parse::<T>()
+                            .map_err(|e| ParsingError::new(e.to_string()))
+                    })
+                    .collect()
+            }
+
+            fn parse_narrowed<T, U>(value: &str) -> Result<Vec<U>, ParsingError>
+            where
+                T: FromStr + Into<U>,
+                T::Err: Display,
+            {
+                parse_elements::<T>(value)
+                    .map(|values| values.into_iter().map(Into::into).collect())
+            }
+
             impl Value {
                 pub fn new<T: Into<DataType>>(
                     vss_type: T,
@@ -135,6 +239,30 @@ pub mod kuksa {
                             .parse::<f64>()
                             .map(Value::Double)
                             .map_err(|e| ParsingError::new(e.to_string())),
+                        DataType::StringArray => parse_array(value)
+                            .map(|values| Value::StringArray(StringArray { values })),
+                        DataType::BooleanArray => parse_elements::<bool>(value)
+                            .map(|values| Value::BoolArray(BoolArray { values })),
+                        DataType::Int8Array => parse_narrowed::<i8, i32>(value)
+                            .map(|values| Value::Int32Array(Int32Array { values })),
+                        DataType::Int16Array => parse_narrowed::<i16, i32>(value)
+                            .map(|values| Value::Int32Array(Int32Array { values })),
+                        DataType::Int32Array => parse_elements::<i32>(value)
+                            .map(|values| Value::Int32Array(Int32Array { values })),
+                        DataType::Int64Array => parse_elements::<i64>(value)
+                            .map(|values| Value::Int64Array(Int64Array { values })),
+                        DataType::Uint8Array => parse_narrowed::<u8, u32>(value)
+                            .map(|values| Value::Uint32Array(Uint32Array { values })),
+                        DataType::Uint16Array => parse_narrowed::<u16, u32>(value)
+                            .map(|values| Value::Uint32Array(Uint32Array { values })),
+                        DataType::Uint32Array => parse_elements::<u32>(value)
+                            .map(|values| Value::Uint32Array(Uint32Array { values })),
+                        DataType::Uint64Array => parse_elements::<u64>(value)
+                            .map(|values| Value::Uint64Array(Uint64Array { values })),
+                        DataType::FloatArray => parse_elements::<f32>(value)
+                            .map(|values| Value::FloatArray(FloatArray { values })),
+                        DataType::DoubleArray => parse_elements::<f64>(value)
+                            .map(|values| Value::DoubleArray(DoubleArray { values })),
                         _ => Err(ParsingError::new(format!(
                             "data type '{:?}' not supported for parsing string into typed value",
                             dt.type_id()
@@ -0,0 +1,161 @@
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use tonic::metadata::{MetadataMap, MetadataValue};
+use tonic::{Code, Request, Response, Status};
+
+use crate::common::ClientError;
+
+pub const AUTHORIZATION_HEADER: &str = "authorization";
+pub const AUTH_UUID_HEADER: &str = "auth-uuid";
+
+type CallbackError = Box<dyn std::error::Error + Send + Sync>;
+type TokenCallback = dyn Fn() -> Result<String, CallbackError> + Send + Sync;
+
+// source of the JWT auth-token sent to the databroker
+#[derive(Clone)]
+pub enum TokenProvider {
+    // the same token for every call
+    Static(String),
+    // the token is read from the file on every call, so it can be rotated on disk
+    File(PathBuf),
+    // the callback is asked for the token on every call, eg: to refresh an expired token
+    Callback(Arc<TokenCallback>),
+}
+
+impl TokenProvider {
+    pub fn callback<F>(callback: F) -> Self
+    where
+        F: Fn() -> Result<String, CallbackError> + Send + Sync + 'static,
+    {
+        TokenProvider::Callback(Arc::new(callback))
+    }
+
+    pub fn token(&self) -> Result<String, ClientError> {
+        match self {
+            TokenProvider::Static(token) => Ok(token.clone()),
+            TokenProvider::File(path) => std::fs::read_to_string(path)
+                .map(|token| token.trim().to_string())
+                .map_err(|error| {
+                    ClientError::Config(format!(
+                        "Can not read token file {}: {}",
+                        path.display(),
+                        error
+                    ))
+                }),
+            TokenProvider::Callback(callback) => callback().map_err(|error| {
+                ClientError::Config(format!("Can not get token from callback: {}", error))
+            }),
+        }
+    }
+}
+
+impl fmt::Debug for TokenProvider {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TokenProvider::Static(_) => f.write_str("Static(..)"),
+            TokenProvider::File(path) => f.debug_tuple("File").field(path).finish(),
+            TokenProvider::Callback(_) => f.write_str("Callback(..)"),
+        }
+    }
+}
+
+// attaches the auth-token (or the auth-uuid the server issued for it) to the requests
+#[derive(Debug, Clone)]
+pub(crate) struct Authorization {
+    provider: TokenProvider,
+    // token attached to the latest request
+    token: Option<String>,
+    // (token, auth-uuid issued by the server for this token)
+    auth_uuid: Option<(String, String)>,
+}
+
+impl Authorization {
+    pub(crate) fn new(provider: TokenProvider) -> Self {
+        Authorization {
+            provider,
+            token: None,
+            auth_uuid: None,
+        }
+    }
+
+    pub(crate) fn auth_uuid(&self) -> Option<&str> {
+        self.auth_uuid.as_ref().map(|(_, uuid)| uuid.as_str())
+    }
+
+    pub(crate) fn insert_into(&mut self, metadata: &mut MetadataMap) -> Result<(), ClientError> {
+        let token = self.provider.token()?;
+
+        match self.auth_uuid {
+            // a refreshed token invalidates the auth-uuid of the previous one
+            Some((ref issued_for, ref uuid)) if *issued_for == token => {
+                let value = MetadataValue::try_from(uuid.as_str())
+                    .map_err(|_| ClientError::Config("Invalid auth-uuid".to_string()))?;
+                metadata.insert(AUTH_UUID_HEADER, value);
+            }
+            _ => {
+                self.auth_uuid = None;
+                let value = MetadataValue::try_from(format!("Bearer {}", token))
+                    .map_err(|_| ClientError::Config("Invalid auth-token".to_string()))?;
+                metadata.insert(AUTHORIZATION_HEADER, value);
+            }
+        }
+
+        self.token = Some(token);
+        Ok(())
+    }
+
+    pub(crate) fn update_from(&mut self, metadata: &MetadataMap) {
+        let uuid = match metadata.get(AUTH_UUID_HEADER).map(|value| value.to_str()) {
+            Some(Ok(uuid)) => uuid.to_string(),
+            _ => return,
+        };
+
+        if let Some(ref token) = self.token {
+            self.auth_uuid = Some((token.clone(), uuid));
+        }
+    }
+
+    // the server rejected the credentials, the next call sends the auth-token again
+    pub(crate) fn reset(&mut self) {
+        self.auth_uuid = None;
+    }
+}
+
+// wrap a message into a request, with the auth-token attached if configured
+pub(crate) fn request<T>(
+    authorization: Option<&mut Authorization>,
+    message: T,
+) -> Result<Request<T>, ClientError> {
+    let mut request = Request::new(message);
+
+    if let Some(authorization) = authorization {
+        authorization.insert_into(request.metadata_mut())?;
+    }
+
+    Ok(request)
+}
+
+// unwrap a response, keeping the auth-uuid issued by the server
+pub(crate) fn response<T>(
+    authorization: Option<&mut Authorization>,
+    result: Result<Response<T>, Status>,
+) -> Result<T, ClientError> {
+    match result {
+        Ok(response) => {
+            if let Some(authorization) = authorization {
+                authorization.update_from(response.metadata());
+            }
+            Ok(response.into_inner())
+        }
+        Err(status) => {
+            if let Some(authorization) = authorization {
+                if status.code() == Code::Unauthenticated {
+                    authorization.reset();
+                }
+            }
+            Err(ClientError::from(status))
+        }
+    }
+}
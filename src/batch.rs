@@ -0,0 +1,56 @@
+use std::collections::HashMap;
+
+use databroker_proto::kuksa::val::v1::{DataEntry, DataEntryError, Error, GetResponse};
+
+use crate::common::ClientError;
+
+// result of KuksaClient::batch_get
+// entries: the returned entries keyed by path (a branch/wildcard request adds one per leaf)
+// errors: the error of every requested path the databroker could not serve
+// error: the response level error, if the databroker returned one next to entries or errors
+#[derive(Debug, Clone, Default)]
+pub struct BatchGet {
+    pub entries: HashMap<String, DataEntry>,
+    pub errors: HashMap<String, Error>,
+    pub error: Option<Error>,
+}
+
+impl BatchGet {
+    pub fn is_complete(&self) -> bool {
+        self.errors.is_empty() && self.error.is_none()
+    }
+}
+
+impl TryFrom<GetResponse> for BatchGet {
+    type Error = ClientError;
+
+    fn try_from(message: GetResponse) -> Result<Self, Self::Error> {
+        let errors = errors_by_path(message.errors);
+
+        // a response level error without anything to attribute it to fails the whole batch
+        if let Some(ref error) = message.error {
+            if errors.is_empty() && message.entries.is_empty() {
+                return Err(ClientError::Function(vec![error.clone()]));
+            }
+        }
+
+        let entries = message
+            .entries
+            .into_iter()
+            .map(|entry| (entry.path.clone(), entry))
+            .collect();
+
+        Ok(BatchGet {
+            entries,
+            errors,
+            error: message.error,
+        })
+    }
+}
+
+pub(crate) fn errors_by_path(errors: Vec<DataEntryError>) -> HashMap<String, Error> {
+    errors
+        .into_iter()
+        .filter_map(|error| Some((error.path, error.error?)))
+        .collect()
+}
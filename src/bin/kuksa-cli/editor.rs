@@ -0,0 +1,157 @@
+// line editor for the shell: rustyline with history, tab completion and an inline hint
+// after the cursor; plain line reading when stdin is not a terminal
+use std::borrow::Cow;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::sync::{Arc, Mutex};
+
+use rustyline::completion::Completer;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::Validator;
+use rustyline::{Context, ExternalPrinter};
+
+pub trait Helper: Send + Sync {
+    // (start of the completed word in bytes, candidates replacing it)
+    fn complete(&self, line: &str, cursor: usize) -> (usize, Vec<String>);
+    // shown dimmed after the line, eg: the datatype of the typed path
+    fn hint(&self, line: &str) -> Option<String>;
+}
+
+pub enum ReadLine {
+    Line(String),
+    // Ctrl-C
+    Interrupted,
+    // Ctrl-D on an empty line, or end of input
+    Eof,
+}
+
+// prints output while a line may be edited, keeping the edited line below the output
+#[derive(Clone)]
+pub struct Printer {
+    external: Option<Arc<Mutex<Box<dyn ExternalPrinter + Send>>>>,
+}
+
+impl Printer {
+    pub fn println(&self, text: &str) {
+        if let Some(ref external) = self.external {
+            let mut external = external.lock().unwrap();
+            if external.print(format!("{}\n", text)).is_ok() {
+                return;
+            }
+        }
+        let _ = writeln!(io::stdout().lock(), "{}", text);
+    }
+}
+
+// adapts the shell's Helper to the rustyline traits
+struct Adapter(Arc<dyn Helper>);
+
+impl Completer for Adapter {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _context: &Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        Ok(self.0.complete(line, pos))
+    }
+}
+
+impl Hinter for Adapter {
+    type Hint = String;
+
+    fn hint(&self, line: &str, pos: usize, _context: &Context<'_>) -> Option<String> {
+        // only at the end of the line, the hint would hide the text after the cursor
+        if pos < line.len() {
+            return None;
+        }
+        self.0.hint(line)
+    }
+}
+
+impl Highlighter for Adapter {
+    fn highlight_hint<'h>(&self, hint: &'h str) -> Cow<'h, str> {
+        Cow::Owned(format!("\x1b[2m{}\x1b[0m", hint))
+    }
+}
+
+impl Validator for Adapter {}
+
+impl rustyline::Helper for Adapter {}
+
+enum Input {
+    Terminal(Box<rustyline::Editor<Adapter, DefaultHistory>>),
+    Lines,
+}
+
+pub struct Editor {
+    input: Input,
+    printer: Printer,
+}
+
+impl Editor {
+    pub fn new(helper: Arc<dyn Helper>) -> io::Result<Self> {
+        if !io::stdin().is_terminal() {
+            return Ok(Editor {
+                input: Input::Lines,
+                printer: Printer { external: None },
+            });
+        }
+
+        let mut editor = rustyline::Editor::new().map_err(into_io_error)?;
+        editor.set_helper(Some(Adapter(helper)));
+        // not available when stdout is not a terminal, printing falls back to stdout
+        let external = editor
+            .create_external_printer()
+            .ok()
+            .map(|printer| Arc::new(Mutex::new(Box::new(printer) as Box<_>)));
+        Ok(Editor {
+            input: Input::Terminal(Box::new(editor)),
+            printer: Printer { external },
+        })
+    }
+
+    pub fn printer(&self) -> Printer {
+        self.printer.clone()
+    }
+
+    // blocking, run it off the async runtime
+    pub fn read_line(&mut self, prompt: &str) -> io::Result<ReadLine> {
+        let editor = match self.input {
+            Input::Terminal(ref mut editor) => editor,
+            Input::Lines => {
+                let mut line = String::new();
+                return match io::stdin().lock().read_line(&mut line)? {
+                    0 => Ok(ReadLine::Eof),
+                    _ => Ok(ReadLine::Line(
+                        line.trim_end_matches(['\r', '\n']).to_string(),
+                    )),
+                };
+            }
+        };
+
+        match editor.readline(prompt) {
+            Ok(line) => {
+                if !line.trim().is_empty() {
+                    editor
+                        .add_history_entry(line.as_str())
+                        .map_err(into_io_error)?;
+                }
+                Ok(ReadLine::Line(line))
+            }
+            Err(rustyline::error::ReadlineError::Interrupted) => Ok(ReadLine::Interrupted),
+            Err(rustyline::error::ReadlineError::Eof) => Ok(ReadLine::Eof),
+            Err(error) => Err(into_io_error(error)),
+        }
+    }
+}
+
+fn into_io_error(error: rustyline::error::ReadlineError) -> io::Error {
+    match error {
+        rustyline::error::ReadlineError::Io(error) => error,
+        error => io::Error::other(error),
+    }
+}
@@ -0,0 +1,396 @@
+// command-line client for the kuksa databroker, eg:
+//   kuksa-cli get Vehicle.Speed Vehicle.Cabin.Door.Row1.Left.IsOpen
+//   KUKSA_TOKEN=... kuksa-cli --json set Vehicle.Speed=88.5
+//   kuksa-cli shell
+
+mod editor;
+mod output;
+mod shell;
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+use clap::{Parser, Subcommand};
+use serde_json::{json, Map, Value as JsonValue};
+use tokio_stream::StreamExt;
+
+use databroker_proto::kuksa::val::v1::{EntryRequest, Field, SubscribeResponse, View};
+use simple_kuksa_client::common::ClientError;
+use simple_kuksa_client::kuksa_client::SubscribeEntry;
+use simple_kuksa_client::vss;
+use simple_kuksa_client::{
+    KuksaClient, SubscriptionEvent, SubscriptionOptions, TlsConfig, TokenProvider,
+};
+
+use output::*;
+
+#[derive(Parser)]
+#[command(
+    name = "kuksa-cli",
+    version,
+    about = "Get, set and subscribe to VSS signals of a kuksa databroker"
+)]
+struct Cli {
+    /// Databroker address
+    #[arg(long, env = "KUKSA_ADDRESS", default_value = "http://127.0.0.1:55555")]
+    address: String,
+
+    /// JWT auth-token
+    #[arg(
+        long,
+        env = "KUKSA_TOKEN",
+        hide_env_values = true,
+        conflicts_with = "token_file"
+    )]
+    token: Option<String>,
+
+    /// File containing the JWT auth-token, re-read on every call
+    #[arg(long, env = "KUKSA_TOKEN_FILE")]
+    token_file: Option<PathBuf>,
+
+    /// CA certificate (PEM) to connect over TLS
+    #[arg(long, env = "KUKSA_CA_CERT")]
+    ca_cert: Option<PathBuf>,
+
+    /// Print JSON instead of text
+    #[arg(long, global = true)]
+    json: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Get the current values of signals
+    Get {
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+    /// Get the target values of actuators
+    GetTarget {
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+    /// Set the current values of signals, eg: Vehicle.Speed=88.5
+    Set {
+        #[arg(required = true, value_name = "PATH=VALUE", value_parser = parse_assignment)]
+        values: Vec<(String, String)>,
+    },
+    /// Set the target values of actuators, eg: Vehicle.Cabin.Door.Row1.Left.IsOpen=true
+    SetTarget {
+        #[arg(required = true, value_name = "PATH=VALUE", value_parser = parse_assignment)]
+        values: Vec<(String, String)>,
+    },
+    /// Print every change of the signals until interrupted
+    Subscribe {
+        #[arg(required = true)]
+        paths: Vec<String>,
+        /// Subscribe to the target values instead of the current values
+        #[arg(long)]
+        target: bool,
+    },
+    /// Print the metadata of signals or branches
+    Metadata {
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+    /// Print the name and version of the databroker
+    ServerInfo,
+    /// Generate rust constants for the signals of a VSS JSON spec
+    Codegen {
+        /// VSS JSON spec, eg: vss_release_3.0.json
+        spec: PathBuf,
+        /// Output file, stdout if omitted
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Interactive shell with path completion and background subscriptions
+    Shell {
+        /// Branch whose signals are completed
+        #[arg(long, default_value = "Vehicle")]
+        root: String,
+    },
+}
+
+fn parse_assignment(input: &str) -> Result<(String, String), String> {
+    match input.split_once('=') {
+        Some((path, value)) if !path.is_empty() => Ok((path.to_string(), value.to_string())),
+        _ => Err(format!("expected PATH=VALUE, got {:?}", input)),
+    }
+}
+
+#[tokio::main]
+async fn main() -> ExitCode {
+    let cli = Cli::parse();
+
+    // works offline, no client
+    if let Command::Codegen {
+        ref spec,
+        ref output,
+    } = cli.command
+    {
+        return match codegen(spec, output.as_deref()) {
+            Ok(()) => ExitCode::SUCCESS,
+            Err(error) => fail(&error),
+        };
+    }
+
+    let client = match client(&cli) {
+        Ok(client) => client,
+        Err(error) => return fail(&error),
+    };
+
+    let result = match cli.command {
+        Command::Get { ref paths } => get(&client, paths, View::CurrentValue, cli.json).await,
+        Command::GetTarget { ref paths } => get(&client, paths, View::TargetValue, cli.json).await,
+        Command::Set { ref values } => set(&client, values, false, cli.json).await,
+        Command::SetTarget { ref values } => set(&client, values, true, cli.json).await,
+        Command::Subscribe { ref paths, target } => {
+            subscribe(&client, paths, target, cli.json).await
+        }
+        Command::Metadata { ref paths } => metadata(&client, paths, cli.json).await,
+        Command::ServerInfo => server_info(&client, cli.json).await,
+        Command::Codegen { .. } => unreachable!("handled before connecting"),
+        Command::Shell { ref root } => shell::run(client, root, cli.json).await,
+    };
+
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        // printed, but some paths failed
+        Ok(false) => ExitCode::FAILURE,
+        Err(error) => fail(&error),
+    }
+}
+
+fn client(cli: &Cli) -> Result<KuksaClient, ClientError> {
+    let mut client = KuksaClient::new(&cli.address);
+
+    if let Some(ref ca_cert) = cli.ca_cert {
+        client = client.with_tls(TlsConfig::new().ca_certificate_file(ca_cert)?);
+    }
+    if let Some(ref token) = cli.token {
+        client = client.with_token(TokenProvider::Static(token.clone()));
+    }
+    if let Some(ref token_file) = cli.token_file {
+        client = client.with_token(TokenProvider::File(token_file.clone()));
+    }
+
+    Ok(client)
+}
+
+fn fail(error: &ClientError) -> ExitCode {
+    eprintln!("error: {}", error);
+    ExitCode::FAILURE
+}
+
+// Ok(false) if a path could not be read
+async fn get(
+    client: &KuksaClient,
+    paths: &[String],
+    view: View,
+    json: bool,
+) -> Result<bool, ClientError> {
+    let field = match view {
+        View::TargetValue => Field::ActuatorTarget,
+        _ => Field::Value,
+    };
+    let entries = paths
+        .iter()
+        .map(|path| EntryRequest {
+            path: path.clone(),
+            view: view.into(),
+            fields: vec![field.into()],
+        })
+        .collect();
+
+    let batch = client.batch_get(entries).await?;
+    let datapoints: BTreeMap<_, _> = batch
+        .entries
+        .iter()
+        .map(|(path, entry)| {
+            let datapoint = match field {
+                Field::ActuatorTarget => entry.actuator_target.as_ref(),
+                _ => entry.value.as_ref(),
+            };
+            (path.clone(), datapoint)
+        })
+        .collect();
+    let errors: BTreeMap<_, _> = batch.errors.iter().collect();
+
+    if json {
+        let mut fields: Map<String, JsonValue> = datapoints
+            .iter()
+            .map(|(path, datapoint)| (path.clone(), datapoint_to_json(*datapoint)))
+            .collect();
+        for (path, error) in &errors {
+            fields.insert(path.to_string(), json!({ "error": error_to_json(error) }));
+        }
+        println!("{}", JsonValue::Object(fields));
+    } else {
+        for (path, datapoint) in &datapoints {
+            println!("{}: {}", path, datapoint_to_string(*datapoint));
+        }
+        for (path, error) in &errors {
+            eprintln!("{}: {}", path, error_to_string(error));
+        }
+    }
+
+    Ok(errors.is_empty())
+}
+
+async fn set(
+    client: &KuksaClient,
+    values: &[(String, String)],
+    target: bool,
+    json: bool,
+) -> Result<bool, ClientError> {
+    // the value strings are parsed with the datatype of each signal
+    let values = values
+        .iter()
+        .map(|(path, value)| (path.as_str(), value.as_str()))
+        .collect();
+
+    let result = match target {
+        true => client.set_target_values(values).await,
+        false => client.set_current_values(values).await,
+    };
+
+    match result {
+        Ok(()) => {
+            if json {
+                println!("{}", json!({ "ok": true }));
+            }
+            Ok(true)
+        }
+        Err(ClientError::Entries(errors)) => {
+            let errors: BTreeMap<_, _> = errors.into_iter().collect();
+            if json {
+                let fields: Map<String, JsonValue> = errors
+                    .iter()
+                    .map(|(path, error)| (path.clone(), json!(error.to_string())))
+                    .collect();
+                println!("{}", json!({ "ok": false, "errors": fields }));
+            } else {
+                for (path, error) in &errors {
+                    match error.path() {
+                        Some(_) => eprintln!("{}", error),
+                        None => eprintln!("{}: {}", path, error),
+                    }
+                }
+            }
+            Ok(false)
+        }
+        Err(error) => Err(error),
+    }
+}
+
+async fn subscribe(
+    client: &KuksaClient,
+    paths: &[String],
+    target: bool,
+    json: bool,
+) -> Result<bool, ClientError> {
+    let (view, field) = match target {
+        true => (View::TargetValue, Field::ActuatorTarget),
+        false => (View::CurrentValue, Field::Value),
+    };
+    let entries = paths
+        .iter()
+        .map(|path| SubscribeEntry {
+            path: path.clone(),
+            view: view.into(),
+            fields: vec![field.into()],
+        })
+        .collect();
+
+    let mut subscription = client.subscribe_managed(entries, SubscriptionOptions::new());
+
+    loop {
+        let event = tokio::select! {
+            event = subscription.next() => event,
+            _ = tokio::signal::ctrl_c() => return Ok(true),
+        };
+
+        match event {
+            Some(SubscriptionEvent::Update(response)) => {
+                for line in update_lines(response, target, json) {
+                    println!("{}", line);
+                }
+            }
+            Some(SubscriptionEvent::Interrupted(error)) => {
+                eprintln!("subscription interrupted: {}", error);
+            }
+            Some(SubscriptionEvent::Resumed { gap }) => {
+                eprintln!("subscription resumed after {:?}", gap);
+            }
+            Some(SubscriptionEvent::Failed(error)) => return Err(error),
+            None => return Ok(true),
+        }
+    }
+}
+
+// one line per updated entry
+fn update_lines(response: SubscribeResponse, target: bool, json: bool) -> Vec<String> {
+    response
+        .updates
+        .into_iter()
+        .filter_map(|update| update.entry)
+        .map(|entry| {
+            let datapoint = match target {
+                true => entry.actuator_target.as_ref(),
+                false => entry.value.as_ref(),
+            };
+            match json {
+                true => json!({ entry.path.clone(): datapoint_to_json(datapoint) }).to_string(),
+                false => format!("{}: {}", entry.path, datapoint_to_string(datapoint)),
+            }
+        })
+        .collect()
+}
+
+async fn metadata(client: &KuksaClient, paths: &[String], json: bool) -> Result<bool, ClientError> {
+    let mut metadatas = BTreeMap::new();
+    for path in paths {
+        metadatas.extend(client.get_metadata(path).await?);
+    }
+
+    if json {
+        let fields: Map<String, JsonValue> = metadatas
+            .iter()
+            .map(|(path, metadata)| (path.clone(), metadata_to_json(metadata)))
+            .collect();
+        println!("{}", JsonValue::Object(fields));
+    } else {
+        for (path, metadata) in &metadatas {
+            println!("{}: {}", path, metadata_to_string(metadata));
+        }
+    }
+
+    Ok(true)
+}
+
+fn codegen(spec: &Path, output: Option<&Path>) -> Result<(), ClientError> {
+    match output {
+        Some(output) => vss::generate_file(spec, output),
+        None => {
+            let json = std::fs::read_to_string(spec).map_err(|error| {
+                ClientError::Parse(format!("Can not read {}: {}", spec.display(), error))
+            })?;
+            print!("{}", vss::generate(&json)?);
+            Ok(())
+        }
+    }
+}
+
+async fn server_info(client: &KuksaClient, json: bool) -> Result<bool, ClientError> {
+    let server_info = client.server_info().await?;
+
+    match json {
+        true => println!("{}", server_info_to_json(&server_info)),
+        false => println!("{}", server_info),
+    }
+
+    Ok(true)
+}
@@ -0,0 +1,168 @@
+// text and JSON rendering of the databroker replies
+use std::fmt::Write;
+
+use serde_json::{json, Map, Value as JsonValue};
+
+use databroker_proto::kuksa::val::v1::{DataType, Datapoint, EntryType, Error, Metadata};
+use simple_kuksa_client::common::Value;
+use simple_kuksa_client::timestamp::timestamp_to_string;
+use simple_kuksa_client::ServerInfo;
+
+// eg: 88.5, true, "text", [1, 2]
+pub fn value_to_string(value: &Value) -> String {
+    fn join<T: ToString>(values: &[T]) -> String {
+        let values: Vec<String> = values.iter().map(ToString::to_string).collect();
+        format!("[{}]", values.join(", "))
+    }
+
+    match value {
+        Value::String(value) => value.clone(),
+        Value::Bool(value) => value.to_string(),
+        Value::Int32(value) => value.to_string(),
+        Value::Int64(value) => value.to_string(),
+        Value::Uint32(value) => value.to_string(),
+        Value::Uint64(value) => value.to_string(),
+        Value::Float(value) => value.to_string(),
+        Value::Double(value) => value.to_string(),
+        Value::StringArray(array) => {
+            let values: Vec<String> = array
+                .values
+                .iter()
+                .map(|value| json!(value).to_string())
+                .collect();
+            format!("[{}]", values.join(", "))
+        }
+        Value::BoolArray(array) => join(&array.values),
+        Value::Int32Array(array) => join(&array.values),
+        Value::Int64Array(array) => join(&array.values),
+        Value::Uint32Array(array) => join(&array.values),
+        Value::Uint64Array(array) => join(&array.values),
+        Value::FloatArray(array) => join(&array.values),
+        Value::DoubleArray(array) => join(&array.values),
+    }
+}
+
+pub fn value_to_json(value: &Value) -> JsonValue {
+    // the shortest representation of the f32, eg: 0.1 and not 0.10000000149011612;
+    // NaN/Infinity are null, JSON has no such numbers
+    fn float(value: f32) -> JsonValue {
+        value
+            .to_string()
+            .parse::<f64>()
+            .map_or(JsonValue::Null, JsonValue::from)
+    }
+
+    match value {
+        Value::String(value) => json!(value),
+        Value::Bool(value) => json!(value),
+        Value::Int32(value) => json!(value),
+        Value::Int64(value) => json!(value),
+        Value::Uint32(value) => json!(value),
+        Value::Uint64(value) => json!(value),
+        Value::Float(value) => float(*value),
+        Value::Double(value) => JsonValue::from(*value),
+        Value::StringArray(array) => json!(array.values),
+        Value::BoolArray(array) => json!(array.values),
+        Value::Int32Array(array) => json!(array.values),
+        Value::Int64Array(array) => json!(array.values),
+        Value::Uint32Array(array) => json!(array.values),
+        Value::Uint64Array(array) => json!(array.values),
+        Value::FloatArray(array) => array.values.iter().copied().map(float).collect(),
+        Value::DoubleArray(array) => array.values.iter().copied().map(JsonValue::from).collect(),
+    }
+}
+
+pub fn datapoint_to_json(datapoint: Option<&Datapoint>) -> JsonValue {
+    let Some(datapoint) = datapoint else {
+        return JsonValue::Null;
+    };
+
+    let mut fields = Map::new();
+    fields.insert(
+        "value".to_string(),
+        datapoint
+            .value
+            .as_ref()
+            .map_or(JsonValue::Null, value_to_json),
+    );
+    if let Some(ref timestamp) = datapoint.timestamp {
+        fields.insert(
+            "timestamp".to_string(),
+            json!(timestamp_to_string(timestamp)),
+        );
+    }
+    JsonValue::Object(fields)
+}
+
+// eg: "88.5 (2024-05-01T12:00:00Z)", "<no value>"
+pub fn datapoint_to_string(datapoint: Option<&Datapoint>) -> String {
+    let value = datapoint
+        .and_then(|datapoint| datapoint.value.as_ref())
+        .map(value_to_string)
+        .unwrap_or_else(|| "<no value>".to_string());
+
+    match datapoint.and_then(|datapoint| datapoint.timestamp.as_ref()) {
+        Some(timestamp) => format!("{} ({})", value, timestamp_to_string(timestamp)),
+        None => value,
+    }
+}
+
+pub fn error_to_json(error: &Error) -> JsonValue {
+    json!({
+        "code": error.code,
+        "reason": error.reason,
+        "message": error.message,
+    })
+}
+
+pub fn error_to_string(error: &Error) -> String {
+    format!("{} {}: {}", error.code, error.reason, error.message)
+}
+
+pub fn data_type_name(metadata: &Metadata) -> String {
+    DataType::try_from(metadata.data_type)
+        .map(|data_type| format!("{:?}", data_type))
+        .unwrap_or_else(|_| metadata.data_type.to_string())
+}
+
+pub fn entry_type_name(metadata: &Metadata) -> String {
+    EntryType::try_from(metadata.entry_type)
+        .map(|entry_type| format!("{:?}", entry_type))
+        .unwrap_or_else(|_| metadata.entry_type.to_string())
+}
+
+pub fn metadata_to_json(metadata: &Metadata) -> JsonValue {
+    let mut fields = Map::new();
+    fields.insert("data_type".to_string(), json!(data_type_name(metadata)));
+    fields.insert("entry_type".to_string(), json!(entry_type_name(metadata)));
+    for (key, value) in [
+        ("description", &metadata.description),
+        ("unit", &metadata.unit),
+        ("comment", &metadata.comment),
+        ("deprecation", &metadata.deprecation),
+    ] {
+        if let Some(value) = value {
+            fields.insert(key.to_string(), json!(value));
+        }
+    }
+    JsonValue::Object(fields)
+}
+
+// eg: "Float Sensor [km/h] Vehicle speed"
+pub fn metadata_to_string(metadata: &Metadata) -> String {
+    let mut text = format!("{} {}", data_type_name(metadata), entry_type_name(metadata));
+    if let Some(ref unit) = metadata.unit {
+        let _ = write!(text, " [{}]", unit);
+    }
+    if let Some(ref description) = metadata.description {
+        let _ = write!(text, " {}", description);
+    }
+    text
+}
+
+pub fn server_info_to_json(server_info: &ServerInfo) -> JsonValue {
+    json!({
+        "name": server_info.name,
+        "version": server_info.version,
+    })
+}
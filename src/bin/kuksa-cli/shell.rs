@@ -0,0 +1,376 @@
+// interactive shell keeping one connection open, eg:
+//   > subscribe Vehicle.Speed
+//   subscription 1 started
+//   > set Vehicle.Speed=88.5
+//   [1] Vehicle.Speed: 88.5 (2024-05-01T12:00:00Z)
+//   > unsubscribe 1
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::Arc;
+
+use clap::{Parser, Subcommand};
+use tokio::task::JoinHandle;
+use tokio_stream::StreamExt;
+
+use databroker_proto::kuksa::val::v1::{Field, Metadata, View};
+use simple_kuksa_client::common::ClientError;
+use simple_kuksa_client::kuksa_client::SubscribeEntry;
+use simple_kuksa_client::{KuksaClient, SubscriptionEvent, SubscriptionOptions};
+
+use crate::editor::{Editor, Helper, Printer, ReadLine};
+use crate::output::{data_type_name, entry_type_name};
+
+const PROMPT: &str = "kuksa> ";
+
+#[derive(Parser)]
+#[command(name = "", no_binary_name = true, disable_version_flag = true)]
+struct Line {
+    #[command(subcommand)]
+    command: ShellCommand,
+}
+
+#[derive(Subcommand)]
+enum ShellCommand {
+    /// Get the current values of signals
+    Get {
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+    /// Get the target values of actuators
+    GetTarget {
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+    /// Set the current values of signals, eg: Vehicle.Speed=88.5
+    Set {
+        #[arg(required = true, value_name = "PATH=VALUE", value_parser = crate::parse_assignment)]
+        values: Vec<(String, String)>,
+    },
+    /// Set the target values of actuators
+    SetTarget {
+        #[arg(required = true, value_name = "PATH=VALUE", value_parser = crate::parse_assignment)]
+        values: Vec<(String, String)>,
+    },
+    /// Print every change of the signals in the background
+    Subscribe {
+        #[arg(required = true)]
+        paths: Vec<String>,
+        /// Subscribe to the target values instead of the current values
+        #[arg(long)]
+        target: bool,
+    },
+    /// Stop background subscriptions
+    Unsubscribe {
+        /// Subscription ids, or "all"
+        #[arg(required = true)]
+        ids: Vec<String>,
+    },
+    /// List the background subscriptions
+    Subscriptions,
+    /// Print the metadata of signals or branches
+    Metadata {
+        #[arg(required = true)]
+        paths: Vec<String>,
+    },
+    /// Print the name and version of the databroker
+    ServerInfo,
+    /// Leave the shell
+    #[command(alias = "quit")]
+    Exit,
+}
+
+const COMMANDS: &[&str] = &[
+    "exit",
+    "get",
+    "get-target",
+    "help",
+    "metadata",
+    "quit",
+    "server-info",
+    "set",
+    "set-target",
+    "subscribe",
+    "subscriptions",
+    "unsubscribe",
+];
+
+struct Subscription {
+    paths: Vec<String>,
+    task: JoinHandle<()>,
+}
+
+pub async fn run(client: KuksaClient, root: &str, json: bool) -> Result<bool, ClientError> {
+    // the tree to complete from, the shell still works without it
+    let signals: BTreeMap<String, Metadata> = match client.get_metadata(root).await {
+        Ok(metadatas) => metadatas.into_iter().collect(),
+        Err(error) => {
+            eprintln!(
+                "no completion, can not get the metadata of {}: {}",
+                root, error
+            );
+            BTreeMap::new()
+        }
+    };
+
+    let mut editor = match Editor::new(Arc::new(Completion { signals })) {
+        Ok(editor) => editor,
+        Err(error) => {
+            eprintln!("error: can not start the line editor: {}", error);
+            return Ok(false);
+        }
+    };
+    let printer = editor.printer();
+    let mut subscriptions: BTreeMap<usize, Subscription> = BTreeMap::new();
+    let mut next_id = 1;
+
+    loop {
+        // the editor blocks on stdin, hand it to a blocking thread and take it back
+        let (returned, line) = match tokio::task::spawn_blocking(move || {
+            let line = editor.read_line(PROMPT);
+            (editor, line)
+        })
+        .await
+        {
+            Ok(result) => result,
+            Err(error) => {
+                eprintln!("error: the line editor failed: {}", error);
+                return Ok(false);
+            }
+        };
+        editor = returned;
+
+        let line = match line {
+            Ok(ReadLine::Line(line)) => line,
+            // like other shells, Ctrl-C only discards the line
+            Ok(ReadLine::Interrupted) => continue,
+            Ok(ReadLine::Eof) => break,
+            Err(error) => {
+                eprintln!("error: can not read the input: {}", error);
+                break;
+            }
+        };
+
+        let words = match split_words(&line) {
+            Ok(words) if words.is_empty() => continue,
+            Ok(words) => words,
+            Err(error) => {
+                eprintln!("error: {}", error);
+                continue;
+            }
+        };
+        let command = match Line::try_parse_from(words) {
+            Ok(line) => line.command,
+            Err(error) => {
+                // also prints the help
+                let _ = error.print();
+                continue;
+            }
+        };
+
+        let result = match command {
+            ShellCommand::Get { ref paths } => {
+                crate::get(&client, paths, View::CurrentValue, json).await
+            }
+            ShellCommand::GetTarget { ref paths } => {
+                crate::get(&client, paths, View::TargetValue, json).await
+            }
+            ShellCommand::Set { ref values } => crate::set(&client, values, false, json).await,
+            ShellCommand::SetTarget { ref values } => crate::set(&client, values, true, json).await,
+            ShellCommand::Subscribe { paths, target } => {
+                let task = subscribe(&client, next_id, &paths, target, json, printer.clone());
+                subscriptions.insert(next_id, Subscription { paths, task });
+                println!("subscription {} started", next_id);
+                next_id += 1;
+                Ok(true)
+            }
+            ShellCommand::Unsubscribe { ref ids } => {
+                unsubscribe(&mut subscriptions, ids);
+                Ok(true)
+            }
+            ShellCommand::Subscriptions => {
+                for (id, subscription) in &subscriptions {
+                    println!("[{}] {}", id, subscription.paths.join(" "));
+                }
+                Ok(true)
+            }
+            ShellCommand::Metadata { ref paths } => crate::metadata(&client, paths, json).await,
+            ShellCommand::ServerInfo => crate::server_info(&client, json).await,
+            ShellCommand::Exit => break,
+        };
+
+        if let Err(error) = result {
+            eprintln!("error: {}", error);
+        }
+    }
+
+    for subscription in subscriptions.into_values() {
+        subscription.task.abort();
+    }
+    Ok(true)
+}
+
+// prints the updates prefixed with the subscription id, above the edited line
+fn subscribe(
+    client: &KuksaClient,
+    id: usize,
+    paths: &[String],
+    target: bool,
+    json: bool,
+    printer: Printer,
+) -> JoinHandle<()> {
+    let (view, field) = match target {
+        true => (View::TargetValue, Field::ActuatorTarget),
+        false => (View::CurrentValue, Field::Value),
+    };
+    let entries = paths
+        .iter()
+        .map(|path| SubscribeEntry {
+            path: path.clone(),
+            view: view.into(),
+            fields: vec![field.into()],
+        })
+        .collect();
+    let mut subscription = client.subscribe_managed(entries, SubscriptionOptions::new());
+
+    tokio::spawn(async move {
+        while let Some(event) = subscription.next().await {
+            match event {
+                SubscriptionEvent::Update(response) => {
+                    for line in crate::update_lines(response, target, json) {
+                        printer.println(&format!("[{}] {}", id, line));
+                    }
+                }
+                SubscriptionEvent::Interrupted(error) => {
+                    printer.println(&format!("[{}] interrupted: {}", id, error));
+                }
+                SubscriptionEvent::Resumed { gap } => {
+                    printer.println(&format!("[{}] resumed after {:?}", id, gap));
+                }
+                SubscriptionEvent::Failed(error) => {
+                    printer.println(&format!("[{}] failed: {}", id, error));
+                    return;
+                }
+            }
+        }
+    })
+}
+
+fn unsubscribe(subscriptions: &mut BTreeMap<usize, Subscription>, ids: &[String]) {
+    if ids.iter().any(|id| id == "all") {
+        for subscription in std::mem::take(subscriptions).into_values() {
+            subscription.task.abort();
+        }
+        return;
+    }
+
+    for id in ids {
+        match id.parse().ok().and_then(|id| subscriptions.remove(&id)) {
+            Some(subscription) => subscription.task.abort(),
+            None => eprintln!("error: no subscription {}", id),
+        }
+    }
+}
+
+// split a line into words, quotes group words and backslash escapes, eg:
+//   set Vehicle.Cabin.Infotainment.Media.Played.Track="Song 2" -> [set, ...Track=Song 2]
+fn split_words(line: &str) -> Result<Vec<String>, String> {
+    let mut words = vec![];
+    let mut word: Option<String> = None;
+    let mut quote: Option<char> = None;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (_, '\\') => {
+                let escaped = chars.next().ok_or("trailing backslash")?;
+                word.get_or_insert_with(String::new).push(escaped);
+            }
+            (Some(_), c) => word.get_or_insert_with(String::new).push(c),
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (None, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    if quote.is_some() {
+        return Err("unterminated quote".to_string());
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+// completes commands and VSS paths, hints the datatype of a complete path
+struct Completion {
+    signals: BTreeMap<String, Metadata>,
+}
+
+impl Completion {
+    // the word under completion: (start in bytes, text)
+    fn word(line: &str, cursor: usize) -> (usize, &str) {
+        let before = &line[..cursor];
+        let start = before
+            .char_indices()
+            .rfind(|(_, c)| c.is_whitespace())
+            .map(|(position, c)| position + c.len_utf8())
+            .unwrap_or(0);
+        (start, &before[start..])
+    }
+
+    // the next segment of every path starting with the prefix, branches end with '.'
+    fn paths(&self, prefix: &str) -> Vec<String> {
+        let candidates: BTreeSet<String> = self
+            .signals
+            .range(prefix.to_string()..)
+            .take_while(|(path, _)| path.starts_with(prefix))
+            .map(|(path, _)| match path[prefix.len()..].find('.') {
+                Some(end) => path[..prefix.len() + end + 1].to_string(),
+                None => path.clone(),
+            })
+            .collect();
+        candidates.into_iter().collect()
+    }
+}
+
+impl Helper for Completion {
+    fn complete(&self, line: &str, cursor: usize) -> (usize, Vec<String>) {
+        let (start, word) = Completion::word(line, cursor);
+
+        if line[..start].trim().is_empty() {
+            let commands = COMMANDS
+                .iter()
+                .filter(|command| command.starts_with(word))
+                .map(|command| format!("{} ", command))
+                .collect();
+            return (start, commands);
+        }
+        // nothing to complete in a value, eg: Vehicle.Speed=8
+        if word.contains('=') || word.starts_with('-') {
+            return (start, vec![]);
+        }
+
+        (start, self.paths(word))
+    }
+
+    fn hint(&self, line: &str) -> Option<String> {
+        let word = line.split_whitespace().last()?;
+        if line.ends_with(char::is_whitespace) {
+            return None;
+        }
+        let path = word.split_once('=').map_or(word, |(path, _)| path);
+        let metadata = self.signals.get(path)?;
+
+        let mut hint = format!(
+            "  <{} {}",
+            data_type_name(metadata),
+            entry_type_name(metadata)
+        );
+        if let Some(ref unit) = metadata.unit {
+            hint.push_str(&format!(" [{}]", unit));
+        }
+        hint.push('>');
+        Some(hint)
+    }
+}
@@ -0,0 +1,330 @@
+// synchronous facade of KuksaClient for tools without an async runtime, eg: test rigs
+//   let client = blocking::KuksaClient::new("http://127.0.0.1:55555")?;
+//   let speed = client.get_current_value("Vehicle.Speed")?;
+//   for update in client.subscribe_current_value("Vehicle.Speed")? { ... }
+// the client owns a tokio runtime, so it must not be used from async code (block_on panics there)
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+use tokio::runtime::Runtime;
+use tokio_stream::StreamExt;
+use tonic::Streaming;
+
+use databroker_proto::kuksa::val::v1::{
+    DataEntry, Datapoint, EntryRequest, EntryUpdate, Metadata, SubscribeEntry, SubscribeResponse,
+};
+
+use crate::auth::TokenProvider;
+use crate::batch::BatchGet;
+use crate::common::{ClientError, Value};
+use crate::confirmation::ConfirmationOptions;
+use crate::connection::{BackoffPolicy, ConnectionState};
+use crate::server_info::ServerInfo;
+use crate::subscription::{self, SubscriptionEvent, SubscriptionOptions};
+use crate::tls::TlsConfig;
+
+// cheap to clone, the clones share the runtime and the connection
+#[derive(Clone)]
+pub struct KuksaClient {
+    inner: crate::KuksaClient,
+    runtime: Arc<Runtime>,
+}
+
+impl KuksaClient {
+    pub fn new(server_address: &str) -> Result<Self, ClientError> {
+        KuksaClient::from_async(crate::KuksaClient::new(server_address))
+    }
+
+    // wrap a configured async client, eg: MockServer::client()
+    pub fn from_async(client: crate::KuksaClient) -> Result<Self, ClientError> {
+        // one worker keeps the channel alive between the calls
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(1)
+            .thread_name("kuksa-blocking")
+            .enable_all()
+            .build()
+            .map_err(|error| {
+                ClientError::Config(format!("Can not start the runtime: {}", error))
+            })?;
+
+        Ok(KuksaClient {
+            inner: client,
+            runtime: Arc::new(runtime),
+        })
+    }
+
+    pub fn with_tls(self, tls_config: TlsConfig) -> Self {
+        self.map(|client| client.with_tls(tls_config))
+    }
+
+    pub fn with_token(self, token_provider: TokenProvider) -> Self {
+        self.map(|client| client.with_token(token_provider))
+    }
+
+    pub fn with_backoff(self, backoff: BackoffPolicy) -> Self {
+        self.map(|client| client.with_backoff(backoff))
+    }
+
+    pub fn with_version_check(self, version_check: bool) -> Self {
+        self.map(|client| client.with_version_check(version_check))
+    }
+
+    pub fn with_restriction_check(self, restriction_check: bool) -> Self {
+        self.map(|client| client.with_restriction_check(restriction_check))
+    }
+
+    pub fn with_metadata_ttl(self, ttl: Option<Duration>) -> Self {
+        self.map(|client| client.with_metadata_ttl(ttl))
+    }
+
+    fn map<F>(self, configure: F) -> Self
+    where
+        F: FnOnce(crate::KuksaClient) -> crate::KuksaClient,
+    {
+        KuksaClient {
+            inner: configure(self.inner),
+            runtime: self.runtime,
+        }
+    }
+
+    // the async client on the same connection
+    pub fn as_async(&self) -> &crate::KuksaClient {
+        &self.inner
+    }
+
+    pub fn connection_state(&self) -> ConnectionState {
+        self.inner.connection_state()
+    }
+
+    pub fn connect(&self) -> Result<(), ClientError> {
+        self.runtime.block_on(self.inner.connect())
+    }
+
+    pub fn reconnect(&self) -> Result<(), ClientError> {
+        self.runtime.block_on(self.inner.reconnect())
+    }
+
+    pub fn server_info(&self) -> Result<ServerInfo, ClientError> {
+        self.runtime.block_on(self.inner.server_info())
+    }
+
+    pub fn get(
+        &self,
+        path: &str,
+        view: i32,
+        fields: Vec<i32>,
+    ) -> Result<Vec<DataEntry>, ClientError> {
+        self.runtime.block_on(self.inner.get(path, view, fields))
+    }
+
+    pub fn batch_get(&self, entries: Vec<EntryRequest>) -> Result<BatchGet, ClientError> {
+        self.runtime.block_on(self.inner.batch_get(entries))
+    }
+
+    pub fn set(&self, entries: Vec<EntryUpdate>) -> Result<(), ClientError> {
+        self.runtime.block_on(self.inner.set(entries))
+    }
+
+    pub fn get_current_value(&self, path: &str) -> Result<Option<Datapoint>, ClientError> {
+        self.runtime.block_on(self.inner.get_current_value(path))
+    }
+
+    pub fn get_target_value(&self, path: &str) -> Result<Option<Datapoint>, ClientError> {
+        self.runtime.block_on(self.inner.get_target_value(path))
+    }
+
+    pub fn set_current_value(&self, path: &str, value: &str) -> Result<(), ClientError> {
+        self.runtime
+            .block_on(self.inner.set_current_value(path, value))
+    }
+
+    pub fn set_current_value_at(
+        &self,
+        path: &str,
+        value: &str,
+        timestamp: SystemTime,
+    ) -> Result<(), ClientError> {
+        self.runtime
+            .block_on(self.inner.set_current_value_at(path, value, timestamp))
+    }
+
+    pub fn set_target_value(&self, path: &str, value: &str) -> Result<(), ClientError> {
+        self.runtime
+            .block_on(self.inner.set_target_value(path, value))
+    }
+
+    pub fn set_target_value_at(
+        &self,
+        path: &str,
+        value: &str,
+        timestamp: SystemTime,
+    ) -> Result<(), ClientError> {
+        self.runtime
+            .block_on(self.inner.set_target_value_at(path, value, timestamp))
+    }
+
+    // see crate::KuksaClient::set_target_value_confirmed
+    pub fn set_target_value_confirmed(
+        &self,
+        path: &str,
+        value: &str,
+        options: ConfirmationOptions,
+    ) -> Result<Value, ClientError> {
+        self.runtime
+            .block_on(self.inner.set_target_value_confirmed(path, value, options))
+    }
+
+    pub fn batch_set_current_values(&self, values: Vec<(&str, Value)>) -> Result<(), ClientError> {
+        self.runtime
+            .block_on(self.inner.batch_set_current_values(values))
+    }
+
+    pub fn batch_set_current_values_at(
+        &self,
+        values: Vec<(&str, Value)>,
+        timestamp: SystemTime,
+    ) -> Result<(), ClientError> {
+        self.runtime
+            .block_on(self.inner.batch_set_current_values_at(values, timestamp))
+    }
+
+    pub fn batch_set_target_values(&self, values: Vec<(&str, Value)>) -> Result<(), ClientError> {
+        self.runtime
+            .block_on(self.inner.batch_set_target_values(values))
+    }
+
+    pub fn batch_set_target_values_at(
+        &self,
+        values: Vec<(&str, Value)>,
+        timestamp: SystemTime,
+    ) -> Result<(), ClientError> {
+        self.runtime
+            .block_on(self.inner.batch_set_target_values_at(values, timestamp))
+    }
+
+    pub fn set_current_values(&self, values: Vec<(&str, &str)>) -> Result<(), ClientError> {
+        self.runtime.block_on(self.inner.set_current_values(values))
+    }
+
+    pub fn set_target_values(&self, values: Vec<(&str, &str)>) -> Result<(), ClientError> {
+        self.runtime.block_on(self.inner.set_target_values(values))
+    }
+
+    pub fn get_metadata(&self, path: &str) -> Result<HashMap<String, Metadata>, ClientError> {
+        self.runtime.block_on(self.inner.get_metadata(path))
+    }
+
+    pub fn prefetch_metadata(&self, branch: &str) -> Result<usize, ClientError> {
+        self.runtime.block_on(self.inner.prefetch_metadata(branch))
+    }
+
+    pub fn invalidate_metadata(&self, path: &str) {
+        self.inner.invalidate_metadata(path);
+    }
+
+    pub fn clear_metadata_cache(&self) {
+        self.inner.clear_metadata_cache();
+    }
+
+    pub fn subscribe(&self, entries: Vec<SubscribeEntry>) -> Result<Subscription, ClientError> {
+        let stream = self.runtime.block_on(self.inner.subscribe(entries))?;
+        Ok(self.subscription(stream))
+    }
+
+    pub fn subscribe_current_value(&self, path: &str) -> Result<Subscription, ClientError> {
+        let stream = self
+            .runtime
+            .block_on(self.inner.subscribe_current_value(path))?;
+        Ok(self.subscription(stream))
+    }
+
+    pub fn subscribe_target_value(&self, path: &str) -> Result<Subscription, ClientError> {
+        let stream = self
+            .runtime
+            .block_on(self.inner.subscribe_target_value(path))?;
+        Ok(self.subscription(stream))
+    }
+
+    // subscription which survives databroker restarts, see SubscriptionEvent
+    pub fn subscribe_managed(
+        &self,
+        entries: Vec<SubscribeEntry>,
+        options: SubscriptionOptions,
+    ) -> ManagedSubscription {
+        // the resubscribing task runs on the runtime of the client
+        let _runtime = self.runtime.enter();
+
+        ManagedSubscription {
+            inner: self.inner.subscribe_managed(entries, options),
+            runtime: self.runtime.clone(),
+        }
+    }
+
+    fn subscription(&self, stream: Streaming<SubscribeResponse>) -> Subscription {
+        Subscription {
+            stream,
+            runtime: self.runtime.clone(),
+        }
+    }
+}
+
+// iterator over the notifications of a subscription, ends when the databroker closes the stream
+pub struct Subscription {
+    stream: Streaming<SubscribeResponse>,
+    runtime: Arc<Runtime>,
+}
+
+impl Subscription {
+    // None if the stream ended, Some(Err(ClientError::Timeout)) if nothing arrived in time;
+    // the subscription stays usable after a timeout
+    pub fn next_timeout(
+        &mut self,
+        timeout: Duration,
+    ) -> Option<Result<SubscribeResponse, ClientError>> {
+        let stream = &mut self.stream;
+        let next = async move { tokio::time::timeout(timeout, stream.next()).await };
+
+        match self.runtime.block_on(next) {
+            Ok(response) => response.map(|response| response.map_err(ClientError::from)),
+            Err(_elapsed) => Some(Err(ClientError::Timeout {
+                path: None,
+                message: format!("no notification within {:?}", timeout),
+                last_value: None,
+            })),
+        }
+    }
+}
+
+impl Iterator for Subscription {
+    type Item = Result<SubscribeResponse, ClientError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let response = self.runtime.block_on(self.stream.next())?;
+        Some(response.map_err(ClientError::from))
+    }
+}
+
+// iterator over the events of KuksaClient::subscribe_managed, ends after SubscriptionEvent::Failed
+pub struct ManagedSubscription {
+    inner: subscription::ManagedSubscription,
+    runtime: Arc<Runtime>,
+}
+
+impl ManagedSubscription {
+    // None if the subscription ended or nothing arrived in time
+    pub fn next_timeout(&mut self, timeout: Duration) -> Option<SubscriptionEvent> {
+        let inner = &mut self.inner;
+        let next = async move { tokio::time::timeout(timeout, inner.next()).await };
+
+        self.runtime.block_on(next).ok().flatten()
+    }
+}
+
+impl Iterator for ManagedSubscription {
+    type Item = SubscriptionEvent;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.runtime.block_on(self.inner.next())
+    }
+}
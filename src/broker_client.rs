@@ -0,0 +1,310 @@
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::time::SystemTime;
+use tokio::sync::watch;
+use tokio_stream::{Stream, StreamExt};
+use tonic::transport::Channel;
+use tonic::Streaming;
+
+use databroker_proto::kuksa::val::v1::Error;
+use databroker_proto::sdv::databroker::v1::broker_client::BrokerClient as ProtoBrokerClient;
+use databroker_proto::sdv::databroker::v1::datapoint::{Failure, Value};
+use databroker_proto::sdv::databroker::v1::{
+    Datapoint, DatapointError, GetDatapointsRequest, GetMetadataRequest, Metadata,
+    SetDatapointsRequest, SubscribeReply, SubscribeRequest,
+};
+
+use crate::auth::{Authorization, TokenProvider};
+use crate::common::ClientError;
+use crate::connection::{no_handshake, BackoffPolicy, ConnectionState, Connector};
+use crate::query::Query;
+use crate::tls::TlsConfig;
+
+// notifications of a query subscription: the selected fields by path
+pub type QueryUpdates =
+    Pin<Box<dyn Stream<Item = Result<HashMap<String, Datapoint>, ClientError>> + Send>>;
+
+// client for the sdv.databroker.v1 Broker service, for databrokers older than kuksa.val.v1
+#[derive(Clone)]
+pub struct BrokerClient {
+    pub server_address: String,
+    connector: Connector,
+}
+
+impl BrokerClient {
+    pub fn new(server_address: &str) -> Self {
+        BrokerClient {
+            server_address: server_address.to_string(),
+            connector: Connector::new("BrokerClient"),
+        }
+    }
+
+    // connect over TLS, the "http://" scheme of the address is upgraded to "https://"
+    pub fn with_tls(mut self, tls_config: TlsConfig) -> Self {
+        self.connector.tls_config = Some(tls_config);
+        self
+    }
+
+    // attach an auth-token to every request
+    pub fn with_token(self, token_provider: TokenProvider) -> Self {
+        *self.connector.authorization() = Some(Authorization::new(token_provider));
+        self
+    }
+
+    // backoff used when (re)connecting to the databroker
+    pub fn with_backoff(mut self, backoff: BackoffPolicy) -> Self {
+        self.connector.backoff = backoff;
+        self
+    }
+
+    pub fn connection_state(&self) -> ConnectionState {
+        self.connector.state()
+    }
+
+    pub fn watch_connection_state(&self) -> watch::Receiver<ConnectionState> {
+        self.connector.watch_state()
+    }
+
+    // connecting is optional: every method connects on first use
+    pub async fn connect(&self) -> Result<(), ClientError> {
+        self.client().await.map(|_| ())
+    }
+
+    // drop the current channel and connect again
+    pub async fn reconnect(&self) -> Result<(), ClientError> {
+        self.connector
+            .reconnect(&self.server_address, no_handshake)
+            .await
+    }
+
+    async fn client(&self) -> Result<(ProtoBrokerClient<Channel>, u64), ClientError> {
+        let (channel, generation) = self
+            .connector
+            .channel(&self.server_address, no_handshake)
+            .await?;
+        Ok((ProtoBrokerClient::new(channel), generation))
+    }
+
+    async fn recover(
+        &self,
+        error: ClientError,
+        generation: u64,
+        retried: &mut bool,
+    ) -> Result<(), ClientError> {
+        self.connector
+            .recover(
+                &self.server_address,
+                error,
+                generation,
+                retried,
+                no_handshake,
+            )
+            .await
+    }
+
+    // current values by path, see split_failures for the failures
+    pub async fn get_datapoints(
+        &self,
+        paths: Vec<&str>,
+    ) -> Result<HashMap<String, Datapoint>, ClientError> {
+        let message = GetDatapointsRequest {
+            datapoints: paths.iter().map(|path| path.to_string()).collect(),
+        };
+        let mut retried = false;
+
+        let reply = loop {
+            let (mut client, generation) = self.client().await?;
+            let request = self.connector.request(message.clone())?;
+
+            match self
+                .connector
+                .response(client.get_datapoints(request).await)
+            {
+                Ok(reply) => break reply,
+                Err(error) => self.recover(error, generation, &mut retried).await?,
+            }
+        };
+
+        split_failures(reply.datapoints)
+    }
+
+    // eg: [("Vehicle.Speed", Value::FloatValue(88.0))], timestamped now
+    pub async fn set_datapoints(&self, values: Vec<(&str, Value)>) -> Result<(), ClientError> {
+        let timestamp = SystemTime::now();
+        let datapoints = values
+            .into_iter()
+            .map(|(path, value)| {
+                let datapoint = Datapoint {
+                    timestamp: Some(timestamp.into()),
+                    value: Some(value),
+                };
+                (path.to_string(), datapoint)
+            })
+            .collect();
+
+        self.set(datapoints).await
+    }
+
+    // errors of the rejected datapoints are keyed by path (ClientError::Entries);
+    // sent once, whether to send it again after a failure is up to the caller
+    pub async fn set(&self, datapoints: HashMap<String, Datapoint>) -> Result<(), ClientError> {
+        // (re)connecting fails before anything is sent
+        let (mut client, _) = self.client().await?;
+        let request = self
+            .connector
+            .request(SetDatapointsRequest { datapoints })?;
+        let reply = self
+            .connector
+            .response(client.set_datapoints(request).await)?;
+
+        if reply.errors.is_empty() {
+            return Ok(());
+        }
+
+        let errors = reply
+            .errors
+            .into_iter()
+            .map(|(path, error)| {
+                let error =
+                    DatapointError::try_from(error).unwrap_or(DatapointError::InternalError);
+                let error = datapoint_error_to_error(&path, error);
+                (path, error)
+            })
+            .collect();
+
+        Err(ClientError::Entries(errors))
+    }
+
+    // metadata of the given paths, all datapoints if paths is empty
+    pub async fn get_metadata(&self, paths: Vec<&str>) -> Result<Vec<Metadata>, ClientError> {
+        let message = GetMetadataRequest {
+            names: paths.iter().map(|path| path.to_string()).collect(),
+        };
+        let mut retried = false;
+
+        loop {
+            let (mut client, generation) = self.client().await?;
+            let request = self.connector.request(message.clone())?;
+
+            match self.connector.response(client.get_metadata(request).await) {
+                Ok(reply) => return Ok(reply.list),
+                Err(error) => self.recover(error, generation, &mut retried).await?,
+            }
+        }
+    }
+
+    // eg: "SELECT Vehicle.Speed WHERE Vehicle.Speed > 50"
+    pub async fn subscribe(&self, query: &str) -> Result<Streaming<SubscribeReply>, ClientError> {
+        let message = SubscribeRequest {
+            query: query.to_string(),
+        };
+        let mut retried = false;
+
+        loop {
+            let (mut client, generation) = self.client().await?;
+            let request = self.connector.request(message.clone())?;
+
+            match self.connector.response(client.subscribe(request).await) {
+                Ok(stream) => return Ok(stream),
+                Err(error) => self.recover(error, generation, &mut retried).await?,
+            }
+        }
+    }
+
+    // subscribe with a validated query, yields the selected fields by path
+    pub async fn subscribe_query(&self, query: &Query) -> Result<QueryUpdates, ClientError> {
+        let stream = self.subscribe(&query.build()?).await?;
+
+        Ok(Box::pin(stream.map(|reply| match reply {
+            Ok(reply) => split_failures(reply.fields),
+            Err(status) => Err(ClientError::from(status)),
+        })))
+    }
+}
+
+// a datapoint without value has no value yet (Failure::NotAvailable),
+// the other failures are returned as errors keyed by path (ClientError::Entries)
+fn split_failures(
+    datapoints: HashMap<String, Datapoint>,
+) -> Result<HashMap<String, Datapoint>, ClientError> {
+    let mut values = HashMap::new();
+    let mut errors = HashMap::new();
+
+    for (path, mut datapoint) in datapoints {
+        match datapoint.value {
+            Some(Value::FailureValue(failure)) => {
+                match Failure::try_from(failure).unwrap_or(Failure::InternalError) {
+                    Failure::NotAvailable => {
+                        datapoint.value = None;
+                        values.insert(path, datapoint);
+                    }
+                    failure => {
+                        let error = failure_to_error(&path, failure);
+                        errors.insert(path, error);
+                    }
+                }
+            }
+            _ => {
+                values.insert(path, datapoint);
+            }
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(values)
+    } else {
+        Err(ClientError::Entries(errors))
+    }
+}
+
+// Datapoint.Failure of the datapoint at path
+pub fn failure_to_error(path: &str, failure: Failure) -> ClientError {
+    let path = path.to_string();
+
+    match failure {
+        Failure::InvalidValue => ClientError::TypeMismatch {
+            path,
+            message: "Datapoint has an invalid value".to_string(),
+        },
+        Failure::UnknownDatapoint => ClientError::NotFound { path },
+        Failure::AccessDenied => ClientError::PermissionDenied {
+            path: Some(path),
+            message: "Access to the datapoint is denied".to_string(),
+        },
+        Failure::NotAvailable => unclassified(404, "not_available", "Datapoint has no value yet"),
+        Failure::InternalError => unclassified(500, "internal_error", "Databroker internal error"),
+    }
+}
+
+// DatapointError of SetDatapoints/UpdateDatapoints for the datapoint at path
+pub fn datapoint_error_to_error(path: &str, error: DatapointError) -> ClientError {
+    let path = path.to_string();
+
+    match error {
+        DatapointError::UnknownDatapoint => ClientError::NotFound { path },
+        DatapointError::InvalidType => ClientError::TypeMismatch {
+            path,
+            message: "Value does not match the datatype".to_string(),
+        },
+        DatapointError::AccessDenied => ClientError::PermissionDenied {
+            path: Some(path),
+            message: "Access to the datapoint is denied".to_string(),
+        },
+        DatapointError::OutOfBounds => ClientError::OutOfRange {
+            path,
+            message: "Value is out of bounds".to_string(),
+        },
+        DatapointError::InternalError => {
+            unclassified(500, "internal_error", "Databroker internal error")
+        }
+    }
+}
+
+// the failures without a ClientError variant of their own, in the shape of a kuksa.val.v1 Error
+fn unclassified(code: u32, reason: &str, message: &str) -> ClientError {
+    ClientError::Function(vec![Error {
+        code,
+        reason: reason.to_string(),
+        message: message.to_string(),
+    }])
+}
@@ -0,0 +1,137 @@
+use std::future::Future;
+use std::time::Duration;
+
+use tokio::time::Instant;
+use tokio_stream::StreamExt;
+
+use crate::common::{ClientError, Value};
+use crate::KuksaClient;
+
+// how KuksaClient::set_target_value_confirmed waits for an actuator
+#[derive(Debug, Clone)]
+pub struct ConfirmationOptions {
+    timeout: Duration,
+    tolerance: f64,
+}
+
+impl Default for ConfirmationOptions {
+    fn default() -> Self {
+        ConfirmationOptions {
+            timeout: Duration::from_secs(10),
+            tolerance: 0.0,
+        }
+    }
+}
+
+impl ConfirmationOptions {
+    pub fn new() -> Self {
+        ConfirmationOptions::default()
+    }
+
+    // how long the current value may take to reach the target, 10s by default
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    // max difference between a numeric current value and the target, exact by default;
+    // other values have to be equal
+    pub fn tolerance(mut self, tolerance: f64) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+}
+
+// subscribe to the current value, set the target with `set` and wait until the current value
+// reaches it; the subscription is opened first, so no change in between is missed
+pub(crate) async fn confirm<F>(
+    client: &KuksaClient,
+    path: &str,
+    target: &Value,
+    options: &ConfirmationOptions,
+    set: F,
+) -> Result<Value, ClientError>
+where
+    F: Future<Output = Result<(), ClientError>>,
+{
+    let deadline = Instant::now() + options.timeout;
+    let mut updates = client.subscribe_current_value(path).await?;
+    set.await?;
+
+    let mut last_value = None;
+    loop {
+        let response = match tokio::time::timeout_at(deadline, updates.next()).await {
+            Ok(Some(Ok(response))) => response,
+            // the wait ends early, the error carries the path and the value seen last
+            Ok(Some(Err(status))) => {
+                let error = ClientError::from(status).with_path(path);
+                return Err(match error.path() {
+                    Some(_) => error,
+                    None => closed(path, target, &error.to_string(), last_value),
+                });
+            }
+            Ok(None) => return Err(closed(path, target, "stream ended", last_value)),
+            Err(_elapsed) => {
+                let message = match last_value {
+                    Some(ref value) => format!(
+                        "current value {:?} did not reach the target {:?} within {:?}",
+                        value, target, options.timeout
+                    ),
+                    None => format!(
+                        "no current value reached the target {:?} within {:?}",
+                        target, options.timeout
+                    ),
+                };
+                return Err(ClientError::Timeout {
+                    path: Some(path.to_string()),
+                    message,
+                    last_value,
+                });
+            }
+        };
+
+        let current = response
+            .updates
+            .into_iter()
+            .filter_map(|update| update.entry)
+            .filter(|entry| entry.path == path)
+            .find_map(|entry| entry.value.and_then(|datapoint| datapoint.value));
+
+        if let Some(current) = current {
+            if reached(&current, target, options.tolerance) {
+                return Ok(current);
+            }
+            last_value = Some(current);
+        }
+    }
+}
+
+fn closed(path: &str, target: &Value, reason: &str, last_value: Option<Value>) -> ClientError {
+    ClientError::Timeout {
+        path: Some(path.to_string()),
+        message: format!(
+            "subscription closed before the target {:?} was reached: {}",
+            target, reason
+        ),
+        last_value,
+    }
+}
+
+fn reached(current: &Value, target: &Value, tolerance: f64) -> bool {
+    match (as_f64(current), as_f64(target)) {
+        (Some(current), Some(target)) => (current - target).abs() <= tolerance,
+        _ => current == target,
+    }
+}
+
+fn as_f64(value: &Value) -> Option<f64> {
+    match *value {
+        Value::Int32(value) => Some(f64::from(value)),
+        Value::Int64(value) => Some(value as f64),
+        Value::Uint32(value) => Some(f64::from(value)),
+        Value::Uint64(value) => Some(value as f64),
+        Value::Float(value) => Some(f64::from(value)),
+        Value::Double(value) => Some(value),
+        _ => None,
+    }
+}
@@ -0,0 +1,347 @@
+use rand::Rng;
+use std::error::Error;
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tokio::sync::watch;
+use tonic::transport::{Channel, Endpoint};
+use tonic::{Code, Request, Response, Status};
+
+use crate::auth::{self, Authorization};
+use crate::common::ClientError;
+use crate::tls::TlsConfig;
+
+// state of the channel between KuksaClient and the databroker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    // never connected, or the last (re)connection attempt gave up
+    Disconnected,
+    // attempt = 0 for the first try, increases with every retry
+    Connecting { attempt: u32 },
+    Connected,
+    // the channel was lost and KuksaClient is establishing a new one
+    Reconnecting { attempt: u32 },
+}
+
+// exponential backoff between (re)connection attempts
+// eg: initial 100ms, multiplier 2.0 --> 100ms, 200ms, 400ms, ... up to max_delay
+#[derive(Debug, Clone)]
+pub struct BackoffPolicy {
+    initial_delay: Duration,
+    max_delay: Duration,
+    multiplier: f64,
+    jitter: f64,
+    max_retries: Option<u32>,
+}
+
+impl Default for BackoffPolicy {
+    fn default() -> Self {
+        BackoffPolicy {
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(10),
+            multiplier: 2.0,
+            jitter: 0.2,
+            max_retries: Some(5),
+        }
+    }
+}
+
+impl BackoffPolicy {
+    pub fn new() -> Self {
+        BackoffPolicy::default()
+    }
+
+    pub fn initial_delay(mut self, delay: Duration) -> Self {
+        self.initial_delay = delay;
+        self
+    }
+
+    pub fn max_delay(mut self, delay: Duration) -> Self {
+        self.max_delay = delay;
+        self
+    }
+
+    pub fn multiplier(mut self, multiplier: f64) -> Self {
+        self.multiplier = multiplier.max(1.0);
+        self
+    }
+
+    // fraction (0.0 - 1.0) of the delay which is randomly removed,
+    // so that many clients do not reconnect at the same time
+    pub fn jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    // None: retry forever
+    pub fn max_retries(mut self, max_retries: Option<u32>) -> Self {
+        self.max_retries = max_retries;
+        self
+    }
+
+    // delay before the given retry (retry >= 1), None if no retry is left
+    pub fn delay(&self, retry: u32) -> Option<Duration> {
+        if let Some(max_retries) = self.max_retries {
+            if retry > max_retries {
+                return None;
+            }
+        }
+
+        let exponent = retry.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let delay = (self.initial_delay.as_secs_f64() * self.multiplier.powi(exponent))
+            .min(self.max_delay.as_secs_f64());
+
+        let jitter = if self.jitter > 0.0 {
+            rand::thread_rng().gen_range(0.0..=self.jitter)
+        } else {
+            0.0
+        };
+
+        Some(Duration::from_secs_f64(delay * (1.0 - jitter)))
+    }
+}
+
+// the call failed because the channel to the databroker is gone
+pub(crate) fn is_connection_lost(status: &Status) -> bool {
+    match status.code() {
+        Code::Unavailable => true,
+        // eg: connection reset while the request was in flight
+        Code::Unknown => status
+            .source()
+            .is_some_and(|source| source.is::<tonic::transport::Error>()),
+        _ => false,
+    }
+}
+
+// endpoint for the server address, the "http://" scheme is upgraded to "https://" for TLS
+pub(crate) fn endpoint(
+    server_address: &str,
+    tls_config: Option<&TlsConfig>,
+) -> Result<Endpoint, ClientError> {
+    let address = match tls_config {
+        Some(_) => match server_address.strip_prefix("http://") {
+            Some(rest) => format!("https://{}", rest),
+            None => server_address.to_string(),
+        },
+        None => server_address.to_string(),
+    };
+
+    let endpoint = Endpoint::from_shared(address)
+        .map_err(|_| ClientError::Config(format!("Invalid server address {}", server_address)))?;
+
+    match tls_config {
+        Some(tls_config) => endpoint
+            .tls_config(tls_config.to_client_tls_config())
+            .map_err(|error| ClientError::Config(format!("Invalid TLS configuration: {}", error))),
+        None => Ok(endpoint),
+    }
+}
+
+// run on every fresh channel before it is handed out, eg: KuksaClient checks the databroker version
+pub(crate) async fn no_handshake(_channel: Channel) -> Result<(), ClientError> {
+    Ok(())
+}
+
+// channel to the databroker shared by the clones of a client: connects on first use, reconnects
+// with backoff and carries the auth-token of every call
+#[derive(Clone)]
+pub(crate) struct Connector {
+    // used in error messages, eg: "BrokerClient"
+    name: &'static str,
+    pub(crate) tls_config: Option<TlsConfig>,
+    pub(crate) backoff: BackoffPolicy,
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    authorization: Mutex<Option<Authorization>>,
+    state: watch::Sender<ConnectionState>,
+    // held while connecting, the calls meanwhile wait for the same connection
+    current: tokio::sync::Mutex<Current>,
+}
+
+// generation: increased with every new channel, so that the calls which failed on the same
+// channel reconnect only once
+#[derive(Default)]
+struct Current {
+    channel: Option<Channel>,
+    generation: u64,
+}
+
+impl Connector {
+    pub(crate) fn new(name: &'static str) -> Self {
+        Connector {
+            name,
+            tls_config: None,
+            backoff: BackoffPolicy::default(),
+            shared: Arc::new(Shared {
+                authorization: Mutex::new(None),
+                state: watch::Sender::new(ConnectionState::Disconnected),
+                current: tokio::sync::Mutex::new(Current::default()),
+            }),
+        }
+    }
+
+    pub(crate) fn state(&self) -> ConnectionState {
+        *self.shared.state.borrow()
+    }
+
+    pub(crate) fn watch_state(&self) -> watch::Receiver<ConnectionState> {
+        self.shared.state.subscribe()
+    }
+
+    pub(crate) fn authorization(&self) -> MutexGuard<'_, Option<Authorization>> {
+        self.shared.authorization.lock().unwrap()
+    }
+
+    // channel for the next call with its generation, connect if needed
+    pub(crate) async fn channel<H, F>(
+        &self,
+        server_address: &str,
+        handshake: H,
+    ) -> Result<(Channel, u64), ClientError>
+    where
+        H: Fn(Channel) -> F,
+        F: Future<Output = Result<(), ClientError>>,
+    {
+        let mut current = self.shared.current.lock().await;
+
+        if current.channel.is_none() {
+            let connected = self.establish(server_address, false, handshake).await?;
+            current.channel = Some(connected);
+            current.generation += 1;
+        }
+
+        match current.channel {
+            Some(ref channel) => Ok((channel.clone(), current.generation)),
+            None => Err(ClientError::Connection(format!(
+                "Can not connect {}",
+                self.name
+            ))),
+        }
+    }
+
+    // drop the current channel and connect again
+    pub(crate) async fn reconnect<H, F>(
+        &self,
+        server_address: &str,
+        handshake: H,
+    ) -> Result<(), ClientError>
+    where
+        H: Fn(Channel) -> F,
+        F: Future<Output = Result<(), ClientError>>,
+    {
+        let mut current = self.shared.current.lock().await;
+        self.replace(&mut current, server_address, handshake).await
+    }
+
+    async fn replace<H, F>(
+        &self,
+        current: &mut Current,
+        server_address: &str,
+        handshake: H,
+    ) -> Result<(), ClientError>
+    where
+        H: Fn(Channel) -> F,
+        F: Future<Output = Result<(), ClientError>>,
+    {
+        current.channel = None;
+        current.generation += 1;
+        current.channel = Some(self.establish(server_address, true, handshake).await?);
+        Ok(())
+    }
+
+    async fn establish<H, F>(
+        &self,
+        server_address: &str,
+        reconnect: bool,
+        handshake: H,
+    ) -> Result<Channel, ClientError>
+    where
+        H: Fn(Channel) -> F,
+        F: Future<Output = Result<(), ClientError>>,
+    {
+        let endpoint = endpoint(server_address, self.tls_config.as_ref())?;
+        let state = &self.shared.state;
+        let mut attempt = 0;
+
+        loop {
+            state.send_replace(match reconnect {
+                true => ConnectionState::Reconnecting { attempt },
+                false => ConnectionState::Connecting { attempt },
+            });
+
+            if let Ok(channel) = endpoint.connect().await {
+                match handshake(channel.clone()).await {
+                    Ok(()) => {
+                        state.send_replace(ConnectionState::Connected);
+                        return Ok(channel);
+                    }
+                    // the databroker went away in between, try again
+                    Err(ref error) if error.is_retryable() => {}
+                    Err(error) => {
+                        state.send_replace(ConnectionState::Disconnected);
+                        return Err(error);
+                    }
+                }
+            }
+
+            attempt += 1;
+            match self.backoff.delay(attempt) {
+                Some(delay) => tokio::time::sleep(delay).await,
+                None => {
+                    state.send_replace(ConnectionState::Disconnected);
+                    return Err(ClientError::Connection(format!(
+                        "Can not connect {}",
+                        self.name
+                    )));
+                }
+            }
+        }
+    }
+
+    // reconnect once if the call failed with a retryable error, eg: the databroker went away,
+    // otherwise give the error back; generation: the one of the channel the call failed on,
+    // if another call replaced that channel already, the call is retried on the new one;
+    // a Timeout is retried on the same channel, the other clones keep using it
+    pub(crate) async fn recover<H, F>(
+        &self,
+        server_address: &str,
+        error: ClientError,
+        generation: u64,
+        retried: &mut bool,
+        handshake: H,
+    ) -> Result<(), ClientError>
+    where
+        H: Fn(Channel) -> F,
+        F: Future<Output = Result<(), ClientError>>,
+    {
+        match error {
+            ClientError::Timeout { .. } if !*retried => {
+                *retried = true;
+                Ok(())
+            }
+            error if error.is_retryable() && !*retried => {
+                *retried = true;
+
+                let mut current = self.shared.current.lock().await;
+                match current.generation == generation {
+                    true => self.replace(&mut current, server_address, handshake).await,
+                    false => Ok(()),
+                }
+            }
+            error => Err(error),
+        }
+    }
+
+    pub(crate) fn request<T>(&self, message: T) -> Result<Request<T>, ClientError> {
+        auth::request(self.authorization().as_mut(), message)
+    }
+
+    pub(crate) fn response<T>(
+        &self,
+        result: Result<Response<T>, Status>,
+    ) -> Result<T, ClientError> {
+        auth::response(self.authorization().as_mut(), result)
+    }
+}
@@ -1,161 +1,541 @@
 use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, SystemTime};
+use tokio::sync::{mpsc, watch};
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::{Stream, StreamExt};
 use tonic::transport::Channel;
-use tonic::Request;
 use tonic::Streaming;
+use tonic::{Code, Request, Response, Status};
 
 use databroker_proto::kuksa::val::v1::val_client::ValClient;
+pub use databroker_proto::kuksa::val::v1::EntryRequest;
 use databroker_proto::kuksa::val::v1::EntryType;
+use databroker_proto::kuksa::val::v1::EntryUpdate;
 use databroker_proto::kuksa::val::v1::Error;
 use databroker_proto::kuksa::val::v1::{DataEntry, Datapoint};
-use databroker_proto::kuksa::val::v1::{EntryRequest, EntryUpdate};
+use databroker_proto::kuksa::val::v1::{DataEntryError, StreamedUpdateRequest};
+use databroker_proto::kuksa::val::v1::{DataType, SetResponse};
 use databroker_proto::kuksa::val::v1::{Field, Metadata, View};
-use databroker_proto::kuksa::val::v1::{GetRequest, SetRequest};
+use databroker_proto::kuksa::val::v1::{GetRequest, GetResponse, GetServerInfoRequest, SetRequest};
 pub use databroker_proto::kuksa::val::v1::{SubscribeEntry, SubscribeRequest, SubscribeResponse};
 
-use crate::common::{datatype_from_metadata, entrytype_from_metadata, str_to_value, ClientError};
-
+use crate::auth::{Authorization, TokenProvider};
+use crate::batch::{errors_by_path, BatchGet};
+use crate::common::{
+    datatype_from_metadata, entrytype_from_metadata, restriction_violation, str_to_value,
+    value_matches_datatype, ClientError, Value,
+};
+use crate::confirmation::{self, ConfirmationOptions};
+use crate::connection::{BackoffPolicy, ConnectionState, Connector};
+use crate::metadata_cache::MetadataCache;
+use crate::server_info::{ServerFeature, ServerInfo, MIN_DATABROKER_VERSION};
+use crate::shadow::Shadow;
+use crate::signal::{Signal, SignalValue};
+use crate::subscription::{ManagedSubscription, SubscriptionOptions};
+use crate::tls::TlsConfig;
+
+// errors of a StreamedUpdate call: Ok(errors) for every batch the databroker rejected entries of,
+// Err when the whole batch or the stream failed
+pub type UpdateErrors =
+    Pin<Box<dyn Stream<Item = Result<Vec<DataEntryError>, ClientError>> + Send>>;
+
+pub const DEFAULT_METADATA_TTL: Duration = Duration::from_secs(60);
+
+// cheap to clone, the clones share the channel, the auth-uuid and the metadata cache,
+// so the calls of many tasks run in parallel over the same connection
+#[derive(Clone)]
 pub struct KuksaClient {
     pub server_address: String,
-    client: Option<ValClient<Channel>>,
+    connector: Connector,
+    version_check: bool,
+    restriction_check: bool,
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    server_info: Mutex<Option<ServerInfo>>,
+    metadata_cache: Mutex<MetadataCache>,
 }
 
 impl KuksaClient {
     pub fn new(server_address: &str) -> Self {
         KuksaClient {
             server_address: server_address.to_string(),
-            client: None,
+            connector: Connector::new("ValClient"),
+            version_check: true,
+            restriction_check: true,
+            shared: Arc::new(Shared {
+                server_info: Mutex::new(None),
+                metadata_cache: Mutex::new(MetadataCache::new(Some(DEFAULT_METADATA_TTL))),
+            }),
         }
     }
 
-    pub async fn connect(&mut self) -> Result<(), ClientError> {
-        if self.client.is_some() {
+    // connect over TLS, the "http://" scheme of the address is upgraded to "https://"
+    pub fn with_tls(mut self, tls_config: TlsConfig) -> Self {
+        self.connector.tls_config = Some(tls_config);
+        self
+    }
+
+    // attach an auth-token to every request
+    pub fn with_token(self, token_provider: TokenProvider) -> Self {
+        *self.connector.authorization() = Some(Authorization::new(token_provider));
+        self
+    }
+
+    // auth-uuid issued by the databroker for the current auth-token, if any
+    pub fn auth_uuid(&self) -> Option<String> {
+        self.connector
+            .authorization()
+            .as_ref()
+            .and_then(|authorization| authorization.auth_uuid())
+            .map(str::to_string)
+    }
+
+    // backoff used when (re)connecting to the databroker
+    pub fn with_backoff(mut self, backoff: BackoffPolicy) -> Self {
+        self.connector.backoff = backoff;
+        self
+    }
+
+    // ask the databroker for its version when connecting and refuse incompatible ones,
+    // enabled by default
+    pub fn with_version_check(mut self, version_check: bool) -> Self {
+        self.version_check = version_check;
+        self
+    }
+
+    // check the values of sets against the ValueRestriction (min/max/allowed values) of the
+    // metadata before sending them, enabled by default; disable it to test the databroker's checks
+    pub fn with_restriction_check(mut self, restriction_check: bool) -> Self {
+        self.restriction_check = restriction_check;
+        self
+    }
+
+    // how long fetched metadata is reused, None: until invalidated, Duration::ZERO: no caching
+    pub fn with_metadata_ttl(self, ttl: Option<Duration>) -> Self {
+        self.metadata_cache().set_ttl(ttl);
+        self
+    }
+
+    pub fn connection_state(&self) -> ConnectionState {
+        self.connector.state()
+    }
+
+    // get notified when the connection state changes, eg: to pause the app while reconnecting
+    pub fn watch_connection_state(&self) -> watch::Receiver<ConnectionState> {
+        self.connector.watch_state()
+    }
+
+    // connecting is optional: every method connects on first use
+    pub async fn connect(&self) -> Result<(), ClientError> {
+        self.client().await.map(|_| ())
+    }
+
+    // drop the current channel and connect again
+    pub async fn reconnect(&self) -> Result<(), ClientError> {
+        self.connector
+            .reconnect(&self.server_address, |channel| self.negotiate(channel))
+            .await
+    }
+
+    // check the databroker version on a fresh channel
+    async fn negotiate(&self, channel: Channel) -> Result<(), ClientError> {
+        if !self.version_check {
             return Ok(());
         }
 
-        ValClient::connect(self.server_address.clone())
+        let mut client = ValClient::new(channel);
+
+        let request = self.request(GetServerInfoRequest {})?;
+        let server_info = match self.response(client.get_server_info(request).await) {
+            Ok(message) => ServerInfo {
+                name: message.name,
+                version: message.version,
+            },
+            Err(ClientError::Status(status)) if status.code() == Code::Unimplemented => {
+                return Err(ClientError::Incompatible(
+                    "Server does not implement the kuksa.val.v1 API".to_string(),
+                ));
+            }
+            Err(error) => return Err(error),
+        };
+
+        if !server_info.is_compatible() {
+            let (major, minor, patch) = MIN_DATABROKER_VERSION;
+            return Err(ClientError::Incompatible(format!(
+                "{} is not supported, databroker {}.{}.{} or newer is required",
+                server_info, major, minor, patch
+            )));
+        }
+
+        *self.shared.server_info.lock().unwrap() = Some(server_info);
+        Ok(())
+    }
+
+    // fail early if the connected databroker is known to miss the feature
+    fn require(&self, feature: ServerFeature) -> Result<(), ClientError> {
+        match *self.shared.server_info.lock().unwrap() {
+            Some(ref server_info) if !server_info.supports(feature) => {
+                let (major, minor, patch) = feature.min_version();
+                Err(ClientError::Incompatible(format!(
+                    "{} requires databroker {}.{}.{} or newer, connected to {}",
+                    feature, major, minor, patch, server_info
+                )))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // ValClient for the next call and the generation of its channel, connect if needed
+    async fn client(&self) -> Result<(ValClient<Channel>, u64), ClientError> {
+        let (channel, generation) = self
+            .connector
+            .channel(&self.server_address, |channel| self.negotiate(channel))
+            .await?;
+        Ok((ValClient::new(channel), generation))
+    }
+
+    async fn recover(
+        &self,
+        error: ClientError,
+        generation: u64,
+        retried: &mut bool,
+    ) -> Result<(), ClientError> {
+        self.connector
+            .recover(
+                &self.server_address,
+                error,
+                generation,
+                retried,
+                |channel| self.negotiate(channel),
+            )
             .await
-            .map(|client| {
-                self.client = Some(client);
-            })
-            .map_err(|_| ClientError::Connection("Can not connect ValClient".to_string()))
+    }
+
+    fn metadata_cache(&self) -> MutexGuard<'_, MetadataCache> {
+        self.shared.metadata_cache.lock().unwrap()
     }
 
     pub async fn get(
-        &mut self,
+        &self,
         path: &str,
         view: i32,
         fields: Vec<i32>,
     ) -> Result<Vec<DataEntry>, ClientError> {
-        let client = match self.client {
-            None => {
-                return Err(ClientError::Connection(
-                    "Please connect to server".to_string(),
-                ));
-            }
-            Some(ref mut client) => client,
-        };
-
         let request = GetRequest {
             entries: vec![EntryRequest {
                 path: path.to_string(),
-                view: view,
-                fields: fields,
+                view,
+                fields,
             }],
         };
 
-        let message = match client.get(Request::new(request)).await {
-            Ok(response) => response.into_inner(),
-            Err(error) => {
-                return Err(ClientError::Status(error));
-            }
-        };
+        let message = self
+            .call_get(request)
+            .await
+            .map_err(|error| error.with_path(path))?;
+        response_errors(message.error, message.errors)?;
+
+        Ok(message.entries)
+    }
 
-        // collect errors from response
-        let mut errors = vec![];
+    // read many entries in a single round-trip,
+    // eg: [EntryRequest { path: "Vehicle.Speed", view: View::CurrentValue, fields: [Field::Value] }, ...]
+    pub async fn batch_get(&self, entries: Vec<EntryRequest>) -> Result<BatchGet, ClientError> {
+        let message = self.call_get(GetRequest { entries }).await?;
 
-        if let Some(err) = message.error {
-            errors.push(err);
+        BatchGet::try_from(message)
+    }
+
+    async fn call_get(&self, request: GetRequest) -> Result<GetResponse, ClientError> {
+        let mut retried = false;
+
+        loop {
+            let (mut client, generation) = self.client().await?;
+            let request = self.request(request.clone())?;
+
+            match self.response(client.get(request).await) {
+                Ok(message) => return Ok(message),
+                Err(error) => self.recover(error, generation, &mut retried).await?,
+            }
         }
+    }
+
+    pub async fn set(&self, entries: Vec<EntryUpdate>) -> Result<(), ClientError> {
+        let message = self.call_set(SetRequest { updates: entries }).await?;
+
+        response_errors(message.error, message.errors)
+    }
+
+    // sent once: the databroker may have applied the set before the call failed,
+    // whether to send it again is up to the caller, see ClientError::is_retryable
+    async fn call_set(&self, request: SetRequest) -> Result<SetResponse, ClientError> {
+        // (re)connecting fails before anything is sent
+        let (mut client, _) = self.client().await?;
+        let request = self.request(request)?;
+
+        self.response(client.set(request).await)
+    }
+
+    // set the current values of many signals in a single SetRequest,
+    // nothing is sent if any path/value is invalid; errors are keyed by path (ClientError::Entries)
+    // Timestamp signals take Value::Int64 nanoseconds since the epoch, see timestamp::system_time_to_nanos
+    pub async fn batch_set_current_values(
+        &self,
+        values: Vec<(&str, Value)>,
+    ) -> Result<(), ClientError> {
+        self.batch_set(values, Field::Value, SystemTime::now(), checked)
+            .await
+    }
+
+    // as batch_set_current_values, with the time the values were measured at the source
+    pub async fn batch_set_current_values_at(
+        &self,
+        values: Vec<(&str, Value)>,
+        timestamp: SystemTime,
+    ) -> Result<(), ClientError> {
+        self.batch_set(values, Field::Value, timestamp, checked)
+            .await
+    }
+
+    // set the target values of many actuators in a single SetRequest, see batch_set_current_values
+    pub async fn batch_set_target_values(
+        &self,
+        values: Vec<(&str, Value)>,
+    ) -> Result<(), ClientError> {
+        self.batch_set(values, Field::ActuatorTarget, SystemTime::now(), checked)
+            .await
+    }
+
+    // as batch_set_target_values, with the time the targets were requested at the source
+    pub async fn batch_set_target_values_at(
+        &self,
+        values: Vec<(&str, Value)>,
+        timestamp: SystemTime,
+    ) -> Result<(), ClientError> {
+        self.batch_set(values, Field::ActuatorTarget, timestamp, checked)
+            .await
+    }
 
-        for error in message.errors {
-            if let Some(err) = error.error {
-                errors.push(err);
+    // as batch_set_current_values, the value strings are parsed with the datatype of each signal,
+    // eg: [("Vehicle.Speed", "88.5"), ("Vehicle.Cabin.Door.Row1.Left.IsOpen", "true")]
+    pub async fn set_current_values(&self, values: Vec<(&str, &str)>) -> Result<(), ClientError> {
+        self.batch_set(values, Field::Value, SystemTime::now(), parsed)
+            .await
+    }
+
+    // as batch_set_target_values, the value strings are parsed with the datatype of each actuator
+    pub async fn set_target_values(&self, values: Vec<(&str, &str)>) -> Result<(), ClientError> {
+        self.batch_set(values, Field::ActuatorTarget, SystemTime::now(), parsed)
+            .await
+    }
+
+    // to_value: the value of the path as a Value of the datatype
+    async fn batch_set<V>(
+        &self,
+        values: Vec<(&str, V)>,
+        field: Field,
+        timestamp: SystemTime,
+        to_value: fn(&str, V, DataType) -> Result<Value, ClientError>,
+    ) -> Result<(), ClientError> {
+        let paths: Vec<&str> = values.iter().map(|(path, _)| *path).collect();
+        let (metadatas, errors) = self.batch_metadata(&paths).await?;
+        let mut errors: HashMap<String, ClientError> = errors
+            .into_iter()
+            .map(|(path, error)| {
+                let error = ClientError::from_entry(&path, error);
+                (path, error)
+            })
+            .collect();
+
+        let mut updates = vec![];
+
+        for (path, value) in values {
+            let metadata = match metadatas.get(path) {
+                Some(metadata) => metadata,
+                None => {
+                    errors
+                        .entry(path.to_string())
+                        .or_insert_with(|| ClientError::NotFound {
+                            path: path.to_string(),
+                        });
+                    continue;
+                }
+            };
+
+            if field == Field::ActuatorTarget && metadata.entry_type != EntryType::Actuator as i32 {
+                errors.insert(
+                    path.to_string(),
+                    ClientError::NotActuator {
+                        path: path.to_string(),
+                    },
+                );
+                continue;
             }
+
+            let datatype = DataType::try_from(metadata.data_type).unwrap_or(DataType::Unspecified);
+            let value = match to_value(path, value, datatype) {
+                Ok(value) => value,
+                Err(error) => {
+                    errors.insert(path.to_string(), error);
+                    continue;
+                }
+            };
+
+            if let Some(message) = self.violation(metadata, &value) {
+                errors.insert(
+                    path.to_string(),
+                    ClientError::OutOfRange {
+                        path: path.to_string(),
+                        message,
+                    },
+                );
+                continue;
+            }
+
+            updates.push(entry_update(path, field, value, timestamp));
         }
 
-        // check if return error or entries' value
-        if errors.len() > 0 {
-            return Err(ClientError::Function(errors));
-        } else {
-            return Ok(message.entries);
+        if !errors.is_empty() {
+            return Err(ClientError::Entries(errors));
+        }
+
+        let message = self.call_set(SetRequest { updates }).await?;
+        let errors: HashMap<String, ClientError> = errors_by_path(message.errors)
+            .into_iter()
+            .map(|(path, error)| {
+                let error = ClientError::from_entry(&path, error);
+                (path, error)
+            })
+            .collect();
+
+        match (errors.is_empty(), message.error) {
+            (false, _) => Err(ClientError::Entries(errors)),
+            (true, Some(error)) => Err(ClientError::Function(vec![error])),
+            (true, None) => Ok(()),
         }
     }
 
-    pub async fn set(&mut self, entries: Vec<EntryUpdate>) -> Result<(), ClientError> {
-        let client = match self.client {
-            None => {
-                return Err(ClientError::Connection(
-                    "Please connect to server".to_string(),
-                ));
+    // name and version of the databroker, eg: databroker 0.4.1
+    pub async fn server_info(&self) -> Result<ServerInfo, ClientError> {
+        let mut retried = false;
+        let message = loop {
+            let (mut client, generation) = self.client().await?;
+            let request = self.request(GetServerInfoRequest {})?;
+
+            match self.response(client.get_server_info(request).await) {
+                Ok(message) => break message,
+                Err(error) => self.recover(error, generation, &mut retried).await?,
             }
-            Some(ref mut client) => client,
         };
 
-        let request = SetRequest { updates: entries };
-
-        let message = match client.set(request).await {
-            Ok(response) => response.into_inner(),
-            Err(err) => return Err(ClientError::Status(err)),
+        let server_info = ServerInfo {
+            name: message.name,
+            version: message.version,
         };
+        *self.shared.server_info.lock().unwrap() = Some(server_info.clone());
 
-        // collect errors from response
-        let mut errors = vec![];
+        Ok(server_info)
+    }
 
-        if let Some(err) = message.error {
-            errors.push(err);
-        }
+    // forward batches of updates over a single StreamedUpdate call,
+    // the returned stream yields the per-entry errors reported by the databroker
+    pub async fn streamed_update<S>(&self, updates: S) -> Result<UpdateErrors, ClientError>
+    where
+        S: Stream<Item = Vec<EntryUpdate>> + Send + 'static,
+    {
+        let (mut client, _) = self.client().await?;
+        self.require(ServerFeature::StreamedUpdate)?;
+        let request = self.request(updates.map(|updates| StreamedUpdateRequest { updates }))?;
+
+        let responses = self.response(client.streamed_update(request).await)?;
+
+        Ok(Box::pin(responses.map(|response| {
+            let message = response.map_err(ClientError::from)?;
 
-        for error in message.errors {
-            if let Some(err) = error.error {
-                errors.push(err);
+            match message.error {
+                Some(error) => Err(ClientError::Function(vec![error])),
+                None => Ok(message.errors),
             }
-        }
+        })))
+    }
 
-        if errors.len() > 0 {
-            return Err(ClientError::Function(errors));
-        } else {
-            return Ok(());
-        }
+    // same as streamed_update, fed through a channel
+    // eg: let (updates, errors) = client.streamed_updater(32).await?; updates.send(batch).await
+    pub async fn streamed_updater(
+        &self,
+        buffer: usize,
+    ) -> Result<(mpsc::Sender<Vec<EntryUpdate>>, UpdateErrors), ClientError> {
+        let (sender, receiver) = mpsc::channel(buffer);
+        let errors = self.streamed_update(ReceiverStream::new(receiver)).await?;
+
+        Ok((sender, errors))
     }
 
     pub async fn subscribe(
-        &mut self,
+        &self,
         entries: Vec<SubscribeEntry>,
     ) -> Result<Streaming<SubscribeResponse>, ClientError> {
-        let client = match self.client {
-            None => {
-                // TODO: connect to server
-                return Err(ClientError::Connection(
-                    "Please connect to server".to_string(),
-                ));
-            }
-            Some(ref mut client) => client,
-        };
-
         let request = SubscribeRequest { entries };
 
-        // call subcribes method
-        match client.subscribe(request).await {
-            Ok(response) => {
-                return Ok(response.into_inner());
-            }
-            Err(err) => {
-                return Err(ClientError::Status(err));
+        let mut retried = false;
+        loop {
+            let (mut client, generation) = self.client().await?;
+            let request = self.request(request.clone())?;
+
+            // call subcribes method
+            match self.response(client.subscribe(request).await) {
+                Ok(stream) => return Ok(stream),
+                Err(error) => self.recover(error, generation, &mut retried).await?,
             }
         }
     }
 
-    pub async fn is_actuator(&mut self, path: &str) -> Result<(), ClientError> {
+    // subscription which survives databroker restarts, see SubscriptionEvent
+    pub fn subscribe_managed(
+        &self,
+        entries: Vec<SubscribeEntry>,
+        options: SubscriptionOptions,
+    ) -> ManagedSubscription {
+        ManagedSubscription::spawn(self.clone(), entries, options)
+    }
+
+    // how a value violates the ValueRestriction of its metadata,
+    // None if the value is allowed or the check is disabled
+    pub(crate) fn violation(&self, metadata: &Metadata, value: &Value) -> Option<String> {
+        if !self.restriction_check {
+            return None;
+        }
+
+        restriction_violation(value, metadata.value_restriction.as_ref()?)
+    }
+
+    // in-memory mirror of the current values of signals/branches, see Shadow
+    // eg: let shadow = client.shadow(&["Vehicle.Cabin.*"]).await?
+    pub async fn shadow(&self, paths: &[&str]) -> Result<Shadow, ClientError> {
+        Shadow::new(self, paths).await
+    }
+
+    // typed handle of a signal, the datatype is checked against the metadata once
+    // eg: let speed = client.signal::<f32>("Vehicle.Speed").await?
+    pub async fn signal<T: SignalValue>(&self, path: &str) -> Result<Signal<T>, ClientError> {
+        Signal::new(self.clone(), path).await
+    }
+
+    // wrap a message into a request carrying the auth-token/auth-uuid
+    fn request<T>(&self, message: T) -> Result<Request<T>, ClientError> {
+        self.connector.request(message)
+    }
+
+    // unwrap a response, keeping the auth-uuid issued by the server
+    fn response<T>(&self, result: Result<Response<T>, Status>) -> Result<T, ClientError> {
+        self.connector.response(result)
+    }
+
+    pub async fn is_actuator(&self, path: &str) -> Result<(), ClientError> {
         let metadatas = match self.get_metadata(path).await {
             Ok(metadatas) => metadatas,
             Err(error) => {
@@ -173,18 +553,24 @@ impl KuksaClient {
 
         match entrytype.get(path) {
             Some(EntryType::Actuator) => Ok(()),
-            _ => Err(ClientError::Function(vec![Error {
-                code: 401,
-                reason: "Entry is not an actuator".to_string(),
-                message: "Entry is not an actuator".to_string(),
-            }])),
+            Some(_) => Err(ClientError::NotActuator {
+                path: path.to_string(),
+            }),
+            // a branch
+            None => Err(ClientError::NotFound {
+                path: path.to_string(),
+            }),
         }
     }
 
     pub async fn get_metadata(
-        &mut self,
+        &self,
         entry_path: &str,
     ) -> Result<HashMap<String, Metadata>, ClientError> {
+        if let Some(metadata) = self.metadata_cache().get(entry_path) {
+            return Ok(HashMap::from([(entry_path.to_string(), metadata)]));
+        }
+
         match self
             .get(
                 entry_path,
@@ -199,6 +585,8 @@ impl KuksaClient {
 
                 for data_entry in data_entries {
                     if let Some(metadata) = data_entry.metadata {
+                        self.metadata_cache()
+                            .insert(data_entry.path.clone(), metadata.clone());
                         result.insert(data_entry.path, metadata);
                     }
                 }
@@ -207,37 +595,84 @@ impl KuksaClient {
             }
             Err(error) => {
                 // Err: can not access GET METADATA method
-                return Err(error);
+                Err(error)
             }
         }
     }
 
-    pub async fn get_current_value(
-        &mut self,
-        path: &str,
-    ) -> Result<Option<Datapoint>, ClientError> {
+    // metadata of many leaf paths, the ones missing from the cache are fetched with one Get
+    async fn batch_metadata(
+        &self,
+        paths: &[&str],
+    ) -> Result<(HashMap<String, Metadata>, HashMap<String, Error>), ClientError> {
+        let mut metadatas = HashMap::new();
+        let mut requests = vec![];
+
+        for path in paths {
+            match self.metadata_cache().get(path) {
+                Some(metadata) => {
+                    metadatas.insert(path.to_string(), metadata);
+                }
+                None => requests.push(EntryRequest {
+                    path: path.to_string(),
+                    view: View::Metadata.into(),
+                    fields: vec![Field::Metadata.into()],
+                }),
+            }
+        }
+
+        if requests.is_empty() {
+            return Ok((metadatas, HashMap::new()));
+        }
+
+        let fetched = self.batch_get(requests).await?;
+        for (path, entry) in fetched.entries {
+            if let Some(metadata) = entry.metadata {
+                self.metadata_cache().insert(path.clone(), metadata.clone());
+                metadatas.insert(path, metadata);
+            }
+        }
+
+        Ok((metadatas, fetched.errors))
+    }
+
+    // fill the metadata cache with every leaf of a branch, eg: "Vehicle.Cabin"
+    // returns the number of cached entries
+    pub async fn prefetch_metadata(&self, branch: &str) -> Result<usize, ClientError> {
+        let metadatas = self.get_metadata(branch).await?;
+
+        Ok(metadatas.len())
+    }
+
+    // forget the cached metadata of a leaf or of every leaf of a branch
+    pub fn invalidate_metadata(&self, path: &str) {
+        self.metadata_cache().invalidate(path);
+    }
+
+    pub fn clear_metadata_cache(&self) {
+        self.metadata_cache().clear();
+    }
+
+    pub async fn get_current_value(&self, path: &str) -> Result<Option<Datapoint>, ClientError> {
         match self
             .get(path, View::CurrentValue.into(), vec![Field::Value.into()])
             .await
         {
             Ok(entries) => {
                 if entries.len() != 1 {
-                    return Err(ClientError::Function(vec![Error {
-                        code: 400,
-                        reason: "Path is not a leaf entry".to_string(),
-                        message: "Ensure your path is a sensor/actuator".to_string(),
-                    }]));
+                    // a branch
+                    Err(ClientError::NotFound {
+                        path: path.to_string(),
+                    })
                 } else {
-                    return Ok(entries[0].value.clone());
+                    Ok(entries[0].value.clone())
                 }
             }
-            Err(error) => {
-                return Err(error);
-            }
+            Err(error) => Err(error),
         }
     }
 
-    pub async fn get_target_value(&mut self, path: &str) -> Result<Option<Datapoint>, ClientError> {
+    pub async fn get_target_value(&self, path: &str) -> Result<Option<Datapoint>, ClientError> {
         self.is_actuator(path).await?;
 
         match self
@@ -250,25 +685,34 @@ impl KuksaClient {
         {
             Ok(entries) => {
                 if entries.len() != 1 {
-                    return Err(ClientError::Function(vec![Error {
-                        code: 400,
-                        reason: "Path is not a leaf entry".to_string(),
-                        message: "Ensure your path is a sensor/actuator".to_string(),
-                    }]));
+                    // a branch
+                    Err(ClientError::NotFound {
+                        path: path.to_string(),
+                    })
                 } else {
-                    return Ok(entries[0].actuator_target.clone());
+                    Ok(entries[0].actuator_target.clone())
                 }
             }
-            Err(error) => {
-                return Err(error);
-            }
+            Err(error) => Err(error),
         }
     }
 
     pub async fn set_current_value(
-        &mut self,
+        &self,
+        entry_path: &str,
+        value: &str,
+    ) -> Result<(), ClientError> {
+        self.set_current_value_at(entry_path, value, SystemTime::now())
+            .await
+    }
+
+    // set the current value with the time it was measured at the source,
+    // eg: a feeder forwarding buffered samples
+    pub async fn set_current_value_at(
+        &self,
         entry_path: &str,
         value: &str,
+        timestamp: SystemTime,
     ) -> Result<(), ClientError> {
         let metadatas = match self.get_metadata(entry_path).await {
             Ok(metadatas) => metadatas,
@@ -286,35 +730,37 @@ impl KuksaClient {
         };
 
         if !datatype.contains_key(entry_path) {
-            return Err(ClientError::Function(vec![Error {
-                code: 401,
-                reason: "Error retrieve metadata".to_string(),
-                message: "Can not found metadata for path, path maybe not a leaf entry".to_string(),
-            }]));
+            return Err(ClientError::NotFound {
+                path: entry_path.to_string(),
+            });
         }
 
         let entry_value = str_to_value(value, datatype[entry_path])?;
-
-        let entry = EntryUpdate {
-            fields: vec![Field::Value as i32],
-            entry: Some(DataEntry {
+        if let Some(message) = self.violation(&metadatas[entry_path], &entry_value) {
+            return Err(ClientError::OutOfRange {
                 path: entry_path.to_string(),
-                value: Some(Datapoint {
-                    timestamp: Some(std::time::SystemTime::now().into()),
-                    value: Some(entry_value),
-                }),
-                metadata: None,
-                actuator_target: None,
-            }),
-        };
+                message,
+            });
+        }
 
-        self.set(vec![entry]).await
+        let entry = entry_update(entry_path, Field::Value, entry_value, timestamp);
+
+        self.set(vec![entry])
+            .await
+            .map_err(|error| error.with_path(entry_path))
+    }
+
+    pub async fn set_target_value(&self, entry_path: &str, value: &str) -> Result<(), ClientError> {
+        self.set_target_value_at(entry_path, value, SystemTime::now())
+            .await
     }
 
-    pub async fn set_target_value(
-        &mut self,
+    // set the target value with the time the request was issued at the source
+    pub async fn set_target_value_at(
+        &self,
         entry_path: &str,
         value: &str,
+        timestamp: SystemTime,
     ) -> Result<(), ClientError> {
         self.is_actuator(entry_path).await?;
 
@@ -334,33 +780,75 @@ impl KuksaClient {
         };
 
         if !datatype.contains_key(entry_path) {
-            return Err(ClientError::Function(vec![Error {
-                code: 401,
-                reason: "Error retrieve metadata".to_string(),
-                message: "Can not found metadata for path, path maybe not a leaf entry".to_string(),
-            }]));
+            return Err(ClientError::NotFound {
+                path: entry_path.to_string(),
+            });
         }
 
         let entry_value = str_to_value(value, datatype[entry_path])?;
+        if let Some(message) = self.violation(&metadatas[entry_path], &entry_value) {
+            return Err(ClientError::OutOfRange {
+                path: entry_path.to_string(),
+                message,
+            });
+        }
+
+        let entry = entry_update(entry_path, Field::ActuatorTarget, entry_value, timestamp);
+
+        self.set(vec![entry])
+            .await
+            .map_err(|error| error.with_path(entry_path))
+    }
 
-        let entry = EntryUpdate {
-            fields: vec![Field::ActuatorTarget as i32],
-            entry: Some(DataEntry {
+    // set the target value of an actuator and wait until its current value reaches it,
+    // the error on timeout carries the last current value (ClientError::Timeout)
+    // eg: set_target_value_confirmed(path, "300", ConfirmationOptions::new().tolerance(2.0))
+    pub async fn set_target_value_confirmed(
+        &self,
+        entry_path: &str,
+        value: &str,
+        options: ConfirmationOptions,
+    ) -> Result<Value, ClientError> {
+        let metadata = self
+            .get_metadata(entry_path)
+            .await?
+            .remove(entry_path)
+            .ok_or_else(|| ClientError::NotFound {
                 path: entry_path.to_string(),
-                value: None,
-                metadata: None,
-                actuator_target: Some(Datapoint {
-                    timestamp: Some(std::time::SystemTime::now().into()),
-                    value: Some(entry_value),
-                }),
-            }),
-        };
+            })?;
+
+        if metadata.entry_type != EntryType::Actuator as i32 {
+            return Err(ClientError::NotActuator {
+                path: entry_path.to_string(),
+            });
+        }
+
+        let datatype = DataType::try_from(metadata.data_type).unwrap_or(DataType::Unspecified);
+        let target = str_to_value(value, datatype)?;
+        if let Some(message) = self.violation(&metadata, &target) {
+            return Err(ClientError::OutOfRange {
+                path: entry_path.to_string(),
+                message,
+            });
+        }
 
-        self.set(vec![entry]).await
+        let entry = entry_update(
+            entry_path,
+            Field::ActuatorTarget,
+            target.clone(),
+            SystemTime::now(),
+        );
+
+        let set = async {
+            self.set(vec![entry])
+                .await
+                .map_err(|error| error.with_path(entry_path))
+        };
+        confirmation::confirm(self, entry_path, &target, &options, set).await
     }
 
     pub async fn subscribe_current_value(
-        &mut self,
+        &self,
         entry_path: &str,
     ) -> Result<Streaming<SubscribeResponse>, ClientError> {
         let entries = vec![SubscribeEntry {
@@ -370,11 +858,13 @@ impl KuksaClient {
         }];
 
         // call subcribes method
-        self.subscribe(entries).await
+        self.subscribe(entries)
+            .await
+            .map_err(|error| error.with_path(entry_path))
     }
 
     pub async fn subscribe_target_value(
-        &mut self,
+        &self,
         entry_path: &str,
     ) -> Result<Streaming<SubscribeResponse>, ClientError> {
         self.is_actuator(entry_path).await?;
@@ -385,6 +875,76 @@ impl KuksaClient {
             fields: vec![Field::ActuatorTarget.into()],
         }];
 
-        self.subscribe(entries).await
+        self.subscribe(entries)
+            .await
+            .map_err(|error| error.with_path(entry_path))
+    }
+}
+
+// the typed values of batch_set_current_values/batch_set_target_values
+fn checked(path: &str, value: Value, datatype: DataType) -> Result<Value, ClientError> {
+    match value_matches_datatype(&value, datatype) {
+        true => Ok(value),
+        false => Err(ClientError::TypeMismatch {
+            path: path.to_string(),
+            message: format!("{:?} is not a valid {:?} value", value, datatype),
+        }),
+    }
+}
+
+// the value strings of set_current_values/set_target_values
+fn parsed(_path: &str, value: &str, datatype: DataType) -> Result<Value, ClientError> {
+    str_to_value(value, datatype)
+}
+
+// the errors of a Get/Set response, the error of a single entry gets its own variant
+// (see ClientError::from_entry), several errors are returned as ClientError::Function
+fn response_errors(error: Option<Error>, errors: Vec<DataEntryError>) -> Result<(), ClientError> {
+    let mut entry_errors: Vec<(String, Error)> = errors
+        .into_iter()
+        .filter_map(|error| Some((error.path, error.error?)))
+        .collect();
+
+    match (entry_errors.pop(), error) {
+        (None, None) => Ok(()),
+        // the response level error repeats the one of the entry
+        (Some((path, error)), _) if entry_errors.is_empty() => {
+            Err(ClientError::from_entry(&path, error))
+        }
+        (last, error) => {
+            let errors = error
+                .into_iter()
+                .chain(entry_errors.into_iter().map(|(_, error)| error))
+                .chain(last.map(|(_, error)| error))
+                .collect();
+            Err(ClientError::Function(errors))
+        }
+    }
+}
+
+// the update of one value, of the current value or of the actuator target depending on the field
+pub(crate) fn entry_update(
+    path: &str,
+    field: Field,
+    value: Value,
+    timestamp: SystemTime,
+) -> EntryUpdate {
+    let datapoint = Some(Datapoint {
+        timestamp: Some(timestamp.into()),
+        value: Some(value),
+    });
+    let (value, actuator_target) = match field {
+        Field::ActuatorTarget => (None, datapoint),
+        _ => (datapoint, None),
+    };
+
+    EntryUpdate {
+        fields: vec![field.into()],
+        entry: Some(DataEntry {
+            path: path.to_string(),
+            value,
+            metadata: None,
+            actuator_target,
+        }),
     }
 }
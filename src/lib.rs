@@ -1,5 +1,36 @@
+pub mod auth;
+pub mod batch;
+pub mod blocking;
+pub mod broker_client;
+pub mod confirmation;
+pub mod connection;
 pub mod kuksa_client;
+mod metadata_cache;
+pub mod provider;
+pub mod query;
+pub mod server_info;
+pub mod shadow;
+pub mod signal;
+pub mod subscription;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod tls;
 pub mod utils;
+pub mod vss;
 
-pub use kuksa_client::KuksaClient;
-pub use utils::common;
\ No newline at end of file
+pub use auth::TokenProvider;
+pub use batch::BatchGet;
+pub use broker_client::{BrokerClient, QueryUpdates};
+pub use confirmation::ConfirmationOptions;
+pub use connection::{BackoffPolicy, ConnectionState};
+pub use kuksa_client::{KuksaClient, UpdateErrors};
+pub use provider::{DatapointErrors, Provider};
+pub use query::{Condition, Literal, Operator, Query};
+pub use server_info::{ServerFeature, ServerInfo};
+pub use shadow::{Shadow, ShadowEntry};
+pub use signal::{Signal, SignalUpdates, SignalValue};
+pub use subscription::{ManagedSubscription, SubscriptionEvent, SubscriptionOptions};
+pub use tls::TlsConfig;
+pub use utils::common;
+pub use utils::timestamp;
+pub use vss::VssSignal;
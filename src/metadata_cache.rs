@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use databroker_proto::kuksa::val::v1::Metadata;
+
+// metadata of leaf entries, keyed by path
+// ttl None: entries never expire, Duration::ZERO: caching is disabled
+#[derive(Debug, Clone)]
+pub(crate) struct MetadataCache {
+    ttl: Option<Duration>,
+    entries: HashMap<String, (Metadata, Instant)>,
+}
+
+impl MetadataCache {
+    pub(crate) fn new(ttl: Option<Duration>) -> Self {
+        MetadataCache {
+            ttl,
+            entries: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn set_ttl(&mut self, ttl: Option<Duration>) {
+        self.ttl = ttl;
+        self.entries.clear();
+    }
+
+    pub(crate) fn get(&self, path: &str) -> Option<Metadata> {
+        let (metadata, fetched) = self.entries.get(path)?;
+
+        match self.ttl {
+            Some(ttl) if fetched.elapsed() >= ttl => None,
+            _ => Some(metadata.clone()),
+        }
+    }
+
+    pub(crate) fn insert(&mut self, path: String, metadata: Metadata) {
+        if self.ttl == Some(Duration::ZERO) {
+            return;
+        }
+
+        self.entries.insert(path, (metadata, Instant::now()));
+    }
+
+    // drop a leaf, or every leaf of a branch, eg: "Vehicle.Cabin"
+    pub(crate) fn invalidate(&mut self, path: &str) {
+        let branch = format!("{}.", path.trim_end_matches(".*"));
+        self.entries
+            .retain(|cached, _| cached != path && !cached.starts_with(&branch));
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
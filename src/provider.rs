@@ -0,0 +1,338 @@
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+use std::time::SystemTime;
+use tokio::sync::{mpsc, watch};
+use tokio_stream::wrappers::{ReceiverStream, UnboundedReceiverStream};
+use tokio_stream::{Stream, StreamExt};
+use tonic::transport::Channel;
+
+use databroker_proto::sdv::databroker::v1::collector_client::CollectorClient;
+use databroker_proto::sdv::databroker::v1::datapoint::Value;
+use databroker_proto::sdv::databroker::v1::{
+    ChangeType, DataType, Datapoint, DatapointError, RegisterDatapointsRequest,
+    RegistrationMetadata, StreamDatapointsRequest, UpdateDatapointsRequest,
+};
+
+use crate::auth::{Authorization, TokenProvider};
+use crate::broker_client::datapoint_error_to_error;
+use crate::common::ClientError;
+use crate::connection::{no_handshake, BackoffPolicy, ConnectionState, Connector};
+use crate::tls::TlsConfig;
+
+// errors of a StreamDatapoints call keyed by path: Ok(errors) for every batch with rejected
+// datapoints, Err when the stream failed
+pub type DatapointErrors =
+    Pin<Box<dyn Stream<Item = Result<HashMap<String, ClientError>, ClientError>> + Send>>;
+
+// feeder on top of the sdv.databroker.v1 Collector service: registers datapoints
+// and updates them by path, the ids assigned by the databroker are kept internally
+#[derive(Clone)]
+pub struct Provider {
+    pub server_address: String,
+    connector: Connector,
+    // path --> id returned by RegisterDatapoints, shared by the clones
+    ids: Arc<RwLock<HashMap<String, i32>>>,
+}
+
+impl Provider {
+    pub fn new(server_address: &str) -> Self {
+        Provider {
+            server_address: server_address.to_string(),
+            connector: Connector::new("Provider"),
+            ids: Default::default(),
+        }
+    }
+
+    // connect over TLS, the "http://" scheme of the address is upgraded to "https://"
+    pub fn with_tls(mut self, tls_config: TlsConfig) -> Self {
+        self.connector.tls_config = Some(tls_config);
+        self
+    }
+
+    // attach an auth-token to every request
+    pub fn with_token(self, token_provider: TokenProvider) -> Self {
+        *self.connector.authorization() = Some(Authorization::new(token_provider));
+        self
+    }
+
+    // backoff used when (re)connecting to the databroker
+    pub fn with_backoff(mut self, backoff: BackoffPolicy) -> Self {
+        self.connector.backoff = backoff;
+        self
+    }
+
+    pub fn connection_state(&self) -> ConnectionState {
+        self.connector.state()
+    }
+
+    pub fn watch_connection_state(&self) -> watch::Receiver<ConnectionState> {
+        self.connector.watch_state()
+    }
+
+    // connecting is optional: every method connects on first use
+    pub async fn connect(&self) -> Result<(), ClientError> {
+        self.client().await.map(|_| ())
+    }
+
+    // drop the current channel and connect again
+    pub async fn reconnect(&self) -> Result<(), ClientError> {
+        self.connector
+            .reconnect(&self.server_address, no_handshake)
+            .await
+    }
+
+    async fn client(&self) -> Result<(CollectorClient<Channel>, u64), ClientError> {
+        let (channel, generation) = self
+            .connector
+            .channel(&self.server_address, no_handshake)
+            .await?;
+        Ok((CollectorClient::new(channel), generation))
+    }
+
+    async fn recover(
+        &self,
+        error: ClientError,
+        generation: u64,
+        retried: &mut bool,
+    ) -> Result<(), ClientError> {
+        self.connector
+            .recover(
+                &self.server_address,
+                error,
+                generation,
+                retried,
+                no_handshake,
+            )
+            .await
+    }
+
+    // id the databroker assigned to a registered path
+    pub fn id(&self, path: &str) -> Option<i32> {
+        self.registered().get(path).copied()
+    }
+
+    // every registered path with its id
+    pub fn ids(&self) -> HashMap<String, i32> {
+        self.registered().clone()
+    }
+
+    fn registered(&self) -> RwLockReadGuard<'_, HashMap<String, i32>> {
+        self.ids.read().unwrap()
+    }
+
+    // register (or re-register) datapoints, returns the ids by path;
+    // names missing from the reply are errors keyed by path (ClientError::Entries)
+    pub async fn register_datapoints(
+        &self,
+        datapoints: Vec<RegistrationMetadata>,
+    ) -> Result<HashMap<String, i32>, ClientError> {
+        let names: Vec<String> = datapoints
+            .iter()
+            .map(|metadata| metadata.name.clone())
+            .collect();
+        let message = RegisterDatapointsRequest { list: datapoints };
+        let mut retried = false;
+
+        let reply = loop {
+            let (mut client, generation) = self.client().await?;
+            let request = self.connector.request(message.clone())?;
+
+            match self
+                .connector
+                .response(client.register_datapoints(request).await)
+            {
+                Ok(reply) => break reply,
+                Err(error) => self.recover(error, generation, &mut retried).await?,
+            }
+        };
+
+        self.ids
+            .write()
+            .unwrap()
+            .extend(reply.results.iter().map(|(name, id)| (name.clone(), *id)));
+
+        let errors: HashMap<String, ClientError> = names
+            .into_iter()
+            .filter(|name| !reply.results.contains_key(name))
+            .map(|name| {
+                let error = ClientError::NotFound { path: name.clone() };
+                (name, error)
+            })
+            .collect();
+
+        if errors.is_empty() {
+            Ok(reply.results)
+        } else {
+            Err(ClientError::Entries(errors))
+        }
+    }
+
+    // eg: ("Vehicle.Speed", DataType::Float, ChangeType::Continuous, "Vehicle speed")
+    pub async fn register_datapoint(
+        &self,
+        path: &str,
+        data_type: DataType,
+        change_type: ChangeType,
+        description: &str,
+    ) -> Result<i32, ClientError> {
+        let metadata = RegistrationMetadata {
+            name: path.to_string(),
+            data_type: data_type.into(),
+            description: description.to_string(),
+            change_type: change_type.into(),
+        };
+
+        let ids = self.register_datapoints(vec![metadata]).await?;
+        ids.get(path).copied().ok_or_else(|| ClientError::NotFound {
+            path: path.to_string(),
+        })
+    }
+
+    // update registered datapoints in one UpdateDatapoints call, timestamped now;
+    // nothing is sent if a path was not registered, errors are keyed by path (ClientError::Entries)
+    pub async fn update_datapoints(&self, values: Vec<(&str, Value)>) -> Result<(), ClientError> {
+        let timestamp = SystemTime::now();
+        let mut datapoints = HashMap::new();
+        let mut errors = HashMap::new();
+
+        let ids = self.ids();
+        for (path, value) in values {
+            match ids.get(path) {
+                Some(id) => {
+                    let datapoint = Datapoint {
+                        timestamp: Some(timestamp.into()),
+                        value: Some(value),
+                    };
+                    datapoints.insert(*id, datapoint);
+                }
+                None => {
+                    errors.insert(path.to_string(), not_registered(path));
+                }
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(ClientError::Entries(errors));
+        }
+
+        // sent once, whether to send it again after a failure is up to the caller;
+        // (re)connecting fails before anything is sent
+        let (mut client, _) = self.client().await?;
+        let request = self
+            .connector
+            .request(UpdateDatapointsRequest { datapoints })?;
+        let reply = self
+            .connector
+            .response(client.update_datapoints(request).await)?;
+
+        if reply.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(ClientError::Entries(errors_by_path(
+                &paths_by_id(&ids),
+                reply.errors,
+            )))
+        }
+    }
+
+    // forward batches of updates over a single StreamDatapoints call, for the paths registered
+    // before the call; unregistered paths are dropped and reported in the returned stream
+    pub async fn stream_datapoints<S>(&self, updates: S) -> Result<DatapointErrors, ClientError>
+    where
+        S: Stream<Item = Vec<(String, Value)>> + Send + 'static,
+    {
+        let ids = self.ids();
+        let paths = paths_by_id(&ids);
+        let (rejected, local_errors) = mpsc::unbounded_channel();
+
+        let requests = updates.filter_map(move |values| {
+            let timestamp = SystemTime::now();
+            let mut datapoints = HashMap::new();
+            let mut errors = HashMap::new();
+
+            for (path, value) in values {
+                match ids.get(&path) {
+                    Some(id) => {
+                        let datapoint = Datapoint {
+                            timestamp: Some(timestamp.into()),
+                            value: Some(value),
+                        };
+                        datapoints.insert(*id, datapoint);
+                    }
+                    None => {
+                        let error = not_registered(&path);
+                        errors.insert(path, error);
+                    }
+                }
+            }
+
+            if !errors.is_empty() {
+                let _ = rejected.send(errors);
+            }
+            match datapoints.is_empty() {
+                true => None,
+                false => Some(StreamDatapointsRequest { datapoints }),
+            }
+        });
+
+        let (mut client, _) = self.client().await?;
+        let request = self.connector.request(requests)?;
+        let replies = self
+            .connector
+            .response(client.stream_datapoints(request).await)?;
+
+        let replies = replies.filter_map(move |reply| match reply {
+            Ok(reply) if reply.errors.is_empty() => None,
+            Ok(reply) => Some(Ok(errors_by_path(&paths, reply.errors))),
+            Err(status) => Some(Err(ClientError::from(status))),
+        });
+
+        Ok(Box::pin(
+            UnboundedReceiverStream::new(local_errors)
+                .map(Ok)
+                .merge(replies),
+        ))
+    }
+
+    // same as stream_datapoints, fed through a channel
+    // eg: let (updates, errors) = provider.datapoint_streamer(32).await?; updates.send(batch).await
+    pub async fn datapoint_streamer(
+        &self,
+        buffer: usize,
+    ) -> Result<(mpsc::Sender<Vec<(String, Value)>>, DatapointErrors), ClientError> {
+        let (sender, receiver) = mpsc::channel(buffer);
+        let errors = self
+            .stream_datapoints(ReceiverStream::new(receiver))
+            .await?;
+
+        Ok((sender, errors))
+    }
+}
+
+fn paths_by_id(ids: &HashMap<String, i32>) -> HashMap<i32, String> {
+    ids.iter().map(|(path, id)| (*id, path.clone())).collect()
+}
+
+// the per-id DatapointError map of the Collector, keyed by path
+fn errors_by_path(
+    paths: &HashMap<i32, String>,
+    errors: HashMap<i32, i32>,
+) -> HashMap<String, ClientError> {
+    errors
+        .into_iter()
+        .map(|(id, error)| {
+            let path = paths.get(&id).cloned().unwrap_or_else(|| id.to_string());
+            let error = DatapointError::try_from(error).unwrap_or(DatapointError::InternalError);
+            let error = datapoint_error_to_error(&path, error);
+            (path, error)
+        })
+        .collect()
+}
+
+// the path was not registered by this provider, so it has no id to update
+fn not_registered(path: &str) -> ClientError {
+    ClientError::NotFound {
+        path: path.to_string(),
+    }
+}
@@ -0,0 +1,279 @@
+use std::fmt;
+
+use crate::common::ClientError;
+
+// query of the sdv.databroker.v1 Broker/Subscribe call,
+// eg: Query::select(["Vehicle.Speed"]).filter(Condition::gt("Vehicle.Speed", 50))
+//     --> "SELECT Vehicle.Speed WHERE Vehicle.Speed > 50"
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    fields: Vec<String>,
+    condition: Option<Condition>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Eq,
+    NotEq,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Operator::Eq => "=",
+            Operator::NotEq => "<>",
+            Operator::Gt => ">",
+            Operator::Ge => ">=",
+            Operator::Lt => "<",
+            Operator::Le => "<=",
+        })
+    }
+}
+
+// right hand side of a comparison
+#[derive(Debug, Clone, PartialEq)]
+pub enum Literal {
+    Bool(bool),
+    Int(i64),
+    Uint(u64),
+    Float(f64),
+    String(String),
+}
+
+impl From<bool> for Literal {
+    fn from(value: bool) -> Self {
+        Literal::Bool(value)
+    }
+}
+
+impl From<i32> for Literal {
+    fn from(value: i32) -> Self {
+        Literal::Int(value.into())
+    }
+}
+
+impl From<i64> for Literal {
+    fn from(value: i64) -> Self {
+        Literal::Int(value)
+    }
+}
+
+impl From<u32> for Literal {
+    fn from(value: u32) -> Self {
+        Literal::Uint(value.into())
+    }
+}
+
+impl From<u64> for Literal {
+    fn from(value: u64) -> Self {
+        Literal::Uint(value)
+    }
+}
+
+impl From<f32> for Literal {
+    fn from(value: f32) -> Self {
+        Literal::Float(value.into())
+    }
+}
+
+impl From<f64> for Literal {
+    fn from(value: f64) -> Self {
+        Literal::Float(value)
+    }
+}
+
+impl From<&str> for Literal {
+    fn from(value: &str) -> Self {
+        Literal::String(value.to_string())
+    }
+}
+
+impl From<String> for Literal {
+    fn from(value: String) -> Self {
+        Literal::String(value)
+    }
+}
+
+impl fmt::Display for Literal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Literal::Bool(value) => write!(f, "{}", value),
+            Literal::Int(value) => write!(f, "{}", value),
+            Literal::Uint(value) => write!(f, "{}", value),
+            // keep the decimal point, eg: 50.0 instead of 50
+            Literal::Float(value) => write!(f, "{:?}", value),
+            Literal::String(value) => write!(f, "'{}'", value.replace('\'', "''")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Compare {
+        path: String,
+        operator: Operator,
+        value: Literal,
+    },
+    And(Box<Condition>, Box<Condition>),
+    Or(Box<Condition>, Box<Condition>),
+    Not(Box<Condition>),
+}
+
+impl Condition {
+    pub fn compare<L: Into<Literal>>(path: &str, operator: Operator, value: L) -> Self {
+        Condition::Compare {
+            path: path.to_string(),
+            operator,
+            value: value.into(),
+        }
+    }
+
+    pub fn eq<L: Into<Literal>>(path: &str, value: L) -> Self {
+        Condition::compare(path, Operator::Eq, value)
+    }
+
+    pub fn not_eq<L: Into<Literal>>(path: &str, value: L) -> Self {
+        Condition::compare(path, Operator::NotEq, value)
+    }
+
+    pub fn gt<L: Into<Literal>>(path: &str, value: L) -> Self {
+        Condition::compare(path, Operator::Gt, value)
+    }
+
+    pub fn ge<L: Into<Literal>>(path: &str, value: L) -> Self {
+        Condition::compare(path, Operator::Ge, value)
+    }
+
+    pub fn lt<L: Into<Literal>>(path: &str, value: L) -> Self {
+        Condition::compare(path, Operator::Lt, value)
+    }
+
+    pub fn le<L: Into<Literal>>(path: &str, value: L) -> Self {
+        Condition::compare(path, Operator::Le, value)
+    }
+
+    pub fn and(self, other: Condition) -> Self {
+        Condition::And(Box::new(self), Box::new(other))
+    }
+
+    pub fn or(self, other: Condition) -> Self {
+        Condition::Or(Box::new(self), Box::new(other))
+    }
+
+    #[allow(clippy::should_implement_trait)]
+    pub fn not(self) -> Self {
+        Condition::Not(Box::new(self))
+    }
+
+    fn validate(&self) -> Result<(), ClientError> {
+        match self {
+            Condition::Compare { path, value, .. } => {
+                validate_path(path)?;
+                match value {
+                    Literal::Float(value) if !value.is_finite() => Err(ClientError::Parse(
+                        format!("{} is not a valid query literal", value),
+                    )),
+                    _ => Ok(()),
+                }
+            }
+            Condition::And(left, right) | Condition::Or(left, right) => {
+                left.validate()?;
+                right.validate()
+            }
+            Condition::Not(condition) => condition.validate(),
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Condition::Compare {
+                path,
+                operator,
+                value,
+            } => write!(f, "{} {} {}", path, operator, value),
+            // parenthesized, so nesting never depends on AND/OR precedence
+            Condition::And(left, right) => write!(f, "({} AND {})", left, right),
+            Condition::Or(left, right) => write!(f, "({} OR {})", left, right),
+            Condition::Not(condition) => write!(f, "NOT ({})", condition),
+        }
+    }
+}
+
+impl Query {
+    pub fn select<I, S>(fields: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Query {
+            fields: fields.into_iter().map(Into::into).collect(),
+            condition: None,
+        }
+    }
+
+    // notify only while the condition holds, conditions of repeated calls are combined with AND
+    pub fn filter(mut self, condition: Condition) -> Self {
+        self.condition = Some(match self.condition.take() {
+            Some(existing) => existing.and(condition),
+            None => condition,
+        });
+        self
+    }
+
+    // the query string, checked for what the databroker would reject anyway
+    pub fn build(&self) -> Result<String, ClientError> {
+        if self.fields.is_empty() {
+            return Err(ClientError::Parse(
+                "Query must select at least one field".to_string(),
+            ));
+        }
+        for field in &self.fields {
+            validate_path(field)?;
+        }
+        if let Some(ref condition) = self.condition {
+            condition.validate()?;
+        }
+
+        Ok(self.to_string())
+    }
+}
+
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "SELECT {}", self.fields.join(", "))?;
+        match self.condition {
+            // the outermost parentheses are redundant
+            Some(Condition::And(ref left, ref right)) => {
+                write!(f, " WHERE {} AND {}", left, right)
+            }
+            Some(Condition::Or(ref left, ref right)) => write!(f, " WHERE {} OR {}", left, right),
+            Some(ref condition) => write!(f, " WHERE {}", condition),
+            None => Ok(()),
+        }
+    }
+}
+
+// a VSS leaf path, eg: "Vehicle.Cabin.Door.Row1.Left.IsOpen"; no wildcards
+fn validate_path(path: &str) -> Result<(), ClientError> {
+    let valid = path.split('.').all(|segment| {
+        !segment.is_empty()
+            && segment
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_')
+    });
+
+    if valid {
+        Ok(())
+    } else {
+        Err(ClientError::Parse(format!(
+            "{:?} is not a valid VSS path",
+            path
+        )))
+    }
+}
@@ -0,0 +1,67 @@
+use std::fmt;
+
+// oldest databroker this client works with
+pub const MIN_DATABROKER_VERSION: (u32, u32, u32) = (0, 3, 0);
+
+// parts of the VAL API which were added after MIN_DATABROKER_VERSION
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ServerFeature {
+    StreamedUpdate,
+}
+
+impl ServerFeature {
+    pub fn min_version(&self) -> (u32, u32, u32) {
+        match self {
+            ServerFeature::StreamedUpdate => (0, 4, 1),
+        }
+    }
+}
+
+impl fmt::Display for ServerFeature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerFeature::StreamedUpdate => f.write_str("StreamedUpdate"),
+        }
+    }
+}
+
+// name and version reported by GetServerInfo, eg: ("databroker", "0.4.1")
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerInfo {
+    pub name: String,
+    pub version: String,
+}
+
+impl ServerInfo {
+    // (major, minor, patch), eg: "0.4.1-dev.0" --> (0, 4, 1)
+    // None if the version does not look like one
+    pub fn semver(&self) -> Option<(u32, u32, u32)> {
+        let version = self.version.trim().trim_start_matches('v');
+        let mut parts = version
+            .split(['.', '-', '+'])
+            .map(|part| part.parse::<u32>());
+
+        match (parts.next(), parts.next(), parts.next()) {
+            (Some(Ok(major)), Some(Ok(minor)), Some(Ok(patch))) => Some((major, minor, patch)),
+            (Some(Ok(major)), Some(Ok(minor)), None) => Some((major, minor, 0)),
+            _ => None,
+        }
+    }
+
+    // a server with an unknown version scheme is assumed to be compatible
+    pub fn is_compatible(&self) -> bool {
+        self.semver()
+            .is_none_or(|version| version >= MIN_DATABROKER_VERSION)
+    }
+
+    pub fn supports(&self, feature: ServerFeature) -> bool {
+        self.semver()
+            .is_none_or(|version| version >= feature.min_version())
+    }
+}
+
+impl fmt::Display for ServerInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.name, self.version)
+    }
+}
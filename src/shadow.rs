@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio_stream::StreamExt;
+
+use databroker_proto::kuksa::val::v1::{Datapoint, Field, SubscribeEntry, SubscribeResponse, View};
+
+use crate::common::{ClientError, Value};
+use crate::subscription::{ManagedSubscription, SubscriptionEvent, SubscriptionOptions};
+use crate::KuksaClient;
+
+// latest current value of a signal in the Shadow
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShadowEntry {
+    pub datapoint: Datapoint,
+    // when the update reached the client
+    pub received: Instant,
+}
+
+impl ShadowEntry {
+    pub fn value(&self) -> Option<&Value> {
+        self.datapoint.value.as_ref()
+    }
+
+    pub fn age(&self) -> Duration {
+        self.received.elapsed()
+    }
+}
+
+// in-memory mirror of the current values of a set of signals, kept up to date by a subscription
+// eg: let shadow = client.shadow(&["Vehicle.Cabin.*", "Vehicle.Speed"]).await?;
+//     let speed = shadow.get("Vehicle.Speed"); // no round-trip
+pub struct Shadow {
+    // one sender per leaf, the leaves are resolved once when the shadow is created
+    signals: Arc<HashMap<String, watch::Sender<Option<ShadowEntry>>>>,
+    live: Arc<watch::Sender<bool>>,
+    task: JoinHandle<()>,
+}
+
+impl Shadow {
+    // resolves when the initial values arrived
+    pub(crate) async fn new(client: &KuksaClient, paths: &[&str]) -> Result<Self, ClientError> {
+        // leaves of the branches/wildcards, unknown paths fail here
+        let mut signals = HashMap::new();
+        for path in paths {
+            for leaf in client.get_metadata(path).await?.into_keys() {
+                signals.insert(leaf, watch::Sender::new(None));
+            }
+        }
+        let signals = Arc::new(signals);
+        let live = Arc::new(watch::Sender::new(false));
+
+        let entries = paths
+            .iter()
+            .map(|path| SubscribeEntry {
+                path: path.to_string(),
+                view: View::CurrentValue.into(),
+                fields: vec![Field::Value.into()],
+            })
+            .collect();
+        // the values missed while the subscription was lost are fetched after resuming
+        let options = SubscriptionOptions::new().replay_latest(true);
+        let mut events = client.subscribe_managed(entries, options);
+
+        match events.next().await {
+            Some(SubscriptionEvent::Failed(error)) => return Err(error),
+            Some(event) => apply(&signals, &live, event),
+            None => {
+                return Err(ClientError::Connection(
+                    "Subscription stream closed".to_string(),
+                ))
+            }
+        }
+
+        let task = tokio::spawn(follow(events, signals.clone(), live.clone()));
+
+        Ok(Shadow {
+            signals,
+            live,
+            task,
+        })
+    }
+
+    // the mirrored leaves
+    pub fn paths(&self) -> Vec<String> {
+        let mut paths: Vec<String> = self.signals.keys().cloned().collect();
+        paths.sort();
+        paths
+    }
+
+    // None if the path is not mirrored or has no value yet
+    pub fn get(&self, path: &str) -> Option<ShadowEntry> {
+        self.signals.get(path)?.borrow().clone()
+    }
+
+    pub fn value(&self, path: &str) -> Option<Value> {
+        self.get(path)?.datapoint.value
+    }
+
+    // false while the subscription is interrupted, the values may be outdated meanwhile
+    pub fn is_live(&self) -> bool {
+        *self.live.borrow()
+    }
+
+    pub fn watch_live(&self) -> watch::Receiver<bool> {
+        self.live.subscribe()
+    }
+
+    // the subscription is live and the value was updated within max_age
+    pub fn is_fresh(&self, path: &str, max_age: Duration) -> bool {
+        self.is_live() && self.get(path).is_some_and(|entry| entry.age() <= max_age)
+    }
+
+    // notified on every update of the signal, None if the path is not mirrored
+    pub fn watch(&self, path: &str) -> Option<watch::Receiver<Option<ShadowEntry>>> {
+        Some(self.signals.get(path)?.subscribe())
+    }
+}
+
+impl Drop for Shadow {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+async fn follow(
+    mut events: ManagedSubscription,
+    signals: Arc<HashMap<String, watch::Sender<Option<ShadowEntry>>>>,
+    live: Arc<watch::Sender<bool>>,
+) {
+    while let Some(event) = events.next().await {
+        apply(&signals, &live, event);
+    }
+}
+
+fn apply(
+    signals: &HashMap<String, watch::Sender<Option<ShadowEntry>>>,
+    live: &watch::Sender<bool>,
+    event: SubscriptionEvent,
+) {
+    match event {
+        SubscriptionEvent::Update(response) => {
+            update(signals, response);
+            live.send_replace(true);
+        }
+        // live again with the Update of the latest values which follows (replay_latest),
+        // until then the values may still be outdated
+        SubscriptionEvent::Resumed { .. } => {}
+        SubscriptionEvent::Interrupted(_) | SubscriptionEvent::Failed(_) => {
+            live.send_replace(false);
+        }
+    }
+}
+
+fn update(
+    signals: &HashMap<String, watch::Sender<Option<ShadowEntry>>>,
+    response: SubscribeResponse,
+) {
+    let received = Instant::now();
+
+    for entry in response
+        .updates
+        .into_iter()
+        .filter_map(|update| update.entry)
+    {
+        let (Some(sender), Some(datapoint)) = (signals.get(&entry.path), entry.value) else {
+            continue;
+        };
+        sender.send_replace(Some(ShadowEntry {
+            datapoint,
+            received,
+        }));
+    }
+}
@@ -0,0 +1,322 @@
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::time::SystemTime;
+
+use tokio_stream::{Stream, StreamExt};
+use tonic::Streaming;
+
+use databroker_proto::kuksa::val::v1::{
+    BoolArray, Datapoint, DoubleArray, EntryType, Field, FloatArray, Int32Array, Int64Array,
+    Metadata, StringArray, SubscribeResponse, Uint32Array, Uint64Array,
+};
+
+use crate::common::{ClientError, DataType, Value};
+use crate::confirmation::{self, ConfirmationOptions};
+use crate::kuksa_client::entry_update;
+use crate::timestamp::{nanos_to_system_time, system_time_to_nanos};
+use crate::KuksaClient;
+
+// the typed values of a Signal, or the error of the subscription stream
+pub type SignalUpdates<T> = Pin<Box<dyn Stream<Item = Result<T, ClientError>> + Send>>;
+
+// rust type carrying the values of one VSS datatype, eg: f32 <-> Float, Vec<u8> <-> Uint8Array
+pub trait SignalValue: Sized + Send + 'static {
+    const DATA_TYPE: DataType;
+
+    fn into_value(self) -> Value;
+
+    // None if the value is not of DATA_TYPE
+    fn from_value(value: Value) -> Option<Self>;
+}
+
+macro_rules! scalar {
+    ($type:ty, $data_type:ident, $variant:ident, $wire:ty) => {
+        impl SignalValue for $type {
+            const DATA_TYPE: DataType = DataType::$data_type;
+
+            fn into_value(self) -> Value {
+                Value::$variant(<$wire>::from(self))
+            }
+
+            fn from_value(value: Value) -> Option<Self> {
+                match value {
+                    Value::$variant(value) => <$type>::try_from(value).ok(),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+macro_rules! array {
+    ($type:ty, $data_type:ident, $variant:ident, $array:ident, $wire:ty) => {
+        impl SignalValue for Vec<$type> {
+            const DATA_TYPE: DataType = DataType::$data_type;
+
+            fn into_value(self) -> Value {
+                let values = self.into_iter().map(<$wire>::from).collect();
+                Value::$variant($array { values })
+            }
+
+            fn from_value(value: Value) -> Option<Self> {
+                match value {
+                    Value::$variant(array) => array
+                        .values
+                        .into_iter()
+                        .map(|value| <$type>::try_from(value).ok())
+                        .collect(),
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+scalar!(String, String, String, String);
+scalar!(bool, Boolean, Bool, bool);
+scalar!(i8, Int8, Int32, i32);
+scalar!(i16, Int16, Int32, i32);
+scalar!(i32, Int32, Int32, i32);
+scalar!(i64, Int64, Int64, i64);
+scalar!(u8, Uint8, Uint32, u32);
+scalar!(u16, Uint16, Uint32, u32);
+scalar!(u32, Uint32, Uint32, u32);
+scalar!(u64, Uint64, Uint64, u64);
+scalar!(f32, Float, Float, f32);
+scalar!(f64, Double, Double, f64);
+
+array!(String, StringArray, StringArray, StringArray, String);
+array!(bool, BooleanArray, BoolArray, BoolArray, bool);
+array!(i8, Int8Array, Int32Array, Int32Array, i32);
+array!(i16, Int16Array, Int32Array, Int32Array, i32);
+array!(i32, Int32Array, Int32Array, Int32Array, i32);
+array!(i64, Int64Array, Int64Array, Int64Array, i64);
+array!(u8, Uint8Array, Uint32Array, Uint32Array, u32);
+array!(u16, Uint16Array, Uint32Array, Uint32Array, u32);
+array!(u32, Uint32Array, Uint32Array, Uint32Array, u32);
+array!(u64, Uint64Array, Uint64Array, Uint64Array, u64);
+array!(f32, FloatArray, FloatArray, FloatArray, f32);
+array!(f64, DoubleArray, DoubleArray, DoubleArray, f64);
+
+// timestamps are carried as nanoseconds since the epoch
+impl SignalValue for SystemTime {
+    const DATA_TYPE: DataType = DataType::Timestamp;
+
+    fn into_value(self) -> Value {
+        Value::Int64(system_time_to_nanos(self))
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Int64(nanos) => Some(nanos_to_system_time(nanos)),
+            _ => None,
+        }
+    }
+}
+
+impl SignalValue for Vec<SystemTime> {
+    const DATA_TYPE: DataType = DataType::TimestampArray;
+
+    fn into_value(self) -> Value {
+        let values = self.into_iter().map(system_time_to_nanos).collect();
+        Value::Int64Array(Int64Array { values })
+    }
+
+    fn from_value(value: Value) -> Option<Self> {
+        match value {
+            Value::Int64Array(array) => {
+                Some(array.values.into_iter().map(nanos_to_system_time).collect())
+            }
+            _ => None,
+        }
+    }
+}
+
+// typed handle of one signal, its datatype is checked once when created, eg:
+//   let speed = client.signal::<f32>("Vehicle.Speed").await?;
+//   speed.set(88.5).await?;
+pub struct Signal<T> {
+    client: KuksaClient,
+    path: String,
+    metadata: Metadata,
+    value_type: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for Signal<T> {
+    fn clone(&self) -> Self {
+        Signal {
+            client: self.client.clone(),
+            path: self.path.clone(),
+            metadata: self.metadata.clone(),
+            value_type: PhantomData,
+        }
+    }
+}
+
+impl<T: SignalValue> Signal<T> {
+    pub(crate) async fn new(client: KuksaClient, path: &str) -> Result<Self, ClientError> {
+        let metadata = client
+            .get_metadata(path)
+            .await?
+            .remove(path)
+            .ok_or_else(|| ClientError::NotFound {
+                path: path.to_string(),
+            })?;
+
+        let data_type = DataType::try_from(metadata.data_type).unwrap_or(DataType::Unspecified);
+        if data_type != T::DATA_TYPE {
+            return Err(ClientError::TypeMismatch {
+                path: path.to_string(),
+                message: format!(
+                    "{:?} signal, the handle carries {:?} values",
+                    data_type,
+                    T::DATA_TYPE
+                ),
+            });
+        }
+
+        Ok(Signal {
+            client,
+            path: path.to_string(),
+            metadata,
+            value_type: PhantomData,
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+
+    // None if the signal has no value yet
+    pub async fn get(&self) -> Result<Option<T>, ClientError> {
+        let datapoint = self.client.get_current_value(&self.path).await?;
+        self.typed(datapoint)
+    }
+
+    // None if no target was set yet
+    pub async fn get_target(&self) -> Result<Option<T>, ClientError> {
+        self.require_actuator()?;
+        let datapoint = self.client.get_target_value(&self.path).await?;
+        self.typed(datapoint)
+    }
+
+    pub async fn set(&self, value: T) -> Result<(), ClientError> {
+        let value = self.checked(value)?;
+        self.client
+            .set(vec![entry_update(
+                &self.path,
+                Field::Value,
+                value,
+                SystemTime::now(),
+            )])
+            .await
+    }
+
+    pub async fn set_target(&self, value: T) -> Result<(), ClientError> {
+        self.require_actuator()?;
+        let value = self.checked(value)?;
+        self.send_target(value).await
+    }
+
+    // set the target and wait until the current value reaches it,
+    // see KuksaClient::set_target_value_confirmed
+    pub async fn set_target_confirmed(
+        &self,
+        value: T,
+        options: ConfirmationOptions,
+    ) -> Result<T, ClientError> {
+        self.require_actuator()?;
+        let target = self.checked(value)?;
+
+        let set = self.send_target(target.clone());
+        let current =
+            confirmation::confirm(&self.client, &self.path, &target, &options, set).await?;
+        typed_value(&self.path, current)
+    }
+
+    async fn send_target(&self, value: Value) -> Result<(), ClientError> {
+        self.client
+            .set(vec![entry_update(
+                &self.path,
+                Field::ActuatorTarget,
+                value,
+                SystemTime::now(),
+            )])
+            .await
+    }
+
+    // every change of the current value, updates without a value are skipped
+    pub async fn subscribe(&self) -> Result<SignalUpdates<T>, ClientError> {
+        let stream = self.client.subscribe_current_value(&self.path).await?;
+        Ok(typed_updates(stream, self.path.clone(), false))
+    }
+
+    // every change of the target value, updates without a value are skipped
+    pub async fn subscribe_target(&self) -> Result<SignalUpdates<T>, ClientError> {
+        self.require_actuator()?;
+        let stream = self.client.subscribe_target_value(&self.path).await?;
+        Ok(typed_updates(stream, self.path.clone(), true))
+    }
+
+    fn checked(&self, value: T) -> Result<Value, ClientError> {
+        let value = value.into_value();
+        match self.client.violation(&self.metadata, &value) {
+            Some(message) => Err(ClientError::OutOfRange {
+                path: self.path.clone(),
+                message,
+            }),
+            None => Ok(value),
+        }
+    }
+
+    fn require_actuator(&self) -> Result<(), ClientError> {
+        match EntryType::try_from(self.metadata.entry_type) {
+            Ok(EntryType::Actuator) => Ok(()),
+            _ => Err(ClientError::NotActuator {
+                path: self.path.clone(),
+            }),
+        }
+    }
+
+    fn typed(&self, datapoint: Option<Datapoint>) -> Result<Option<T>, ClientError> {
+        match datapoint.and_then(|datapoint| datapoint.value) {
+            Some(value) => typed_value(&self.path, value).map(Some),
+            None => Ok(None),
+        }
+    }
+}
+
+fn typed_value<T: SignalValue>(path: &str, value: Value) -> Result<T, ClientError> {
+    T::from_value(value.clone()).ok_or_else(|| ClientError::TypeMismatch {
+        path: path.to_string(),
+        message: format!("{:?} is not a {:?} value", value, T::DATA_TYPE),
+    })
+}
+
+fn typed_updates<T: SignalValue>(
+    stream: Streaming<SubscribeResponse>,
+    path: String,
+    target: bool,
+) -> SignalUpdates<T> {
+    Box::pin(stream.filter_map(move |response| {
+        let response = match response {
+            Ok(response) => response,
+            Err(status) => return Some(Err(ClientError::from(status))),
+        };
+
+        let value = response
+            .updates
+            .into_iter()
+            .filter_map(|update| update.entry)
+            .filter(|entry| entry.path == path)
+            .find_map(|entry| match target {
+                true => entry.actuator_target.and_then(|datapoint| datapoint.value),
+                false => entry.value.and_then(|datapoint| datapoint.value),
+            })?;
+        Some(typed_value(&path, value))
+    }))
+}
@@ -0,0 +1,192 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tokio_stream::{Stream, StreamExt};
+
+use databroker_proto::kuksa::val::v1::{EntryUpdate, SubscribeEntry, SubscribeResponse};
+
+use crate::common::ClientError;
+use crate::connection::BackoffPolicy;
+use crate::KuksaClient;
+
+// what a ManagedSubscription hands to the consumer
+#[derive(Debug, Clone)]
+pub enum SubscriptionEvent {
+    // notification from the databroker, or the latest values re-emitted after a resume
+    Update(SubscribeResponse),
+    // the stream was lost, updates are missed until Resumed
+    Interrupted(ClientError),
+    // subscribed again, gap = time since Interrupted
+    Resumed { gap: Duration },
+    // the subscription can not be restored, no more events follow
+    Failed(ClientError),
+}
+
+#[derive(Debug, Clone)]
+pub struct SubscriptionOptions {
+    replay_latest: bool,
+    backoff: BackoffPolicy,
+}
+
+impl Default for SubscriptionOptions {
+    fn default() -> Self {
+        SubscriptionOptions {
+            replay_latest: false,
+            backoff: BackoffPolicy::new().max_retries(None),
+        }
+    }
+}
+
+impl SubscriptionOptions {
+    pub fn new() -> Self {
+        SubscriptionOptions::default()
+    }
+
+    // after Resumed, get the current values and emit them as an Update
+    pub fn replay_latest(mut self, replay_latest: bool) -> Self {
+        self.replay_latest = replay_latest;
+        self
+    }
+
+    // delay between resubscribe attempts, retries forever by default
+    pub fn backoff(mut self, backoff: BackoffPolicy) -> Self {
+        self.backoff = backoff;
+        self
+    }
+}
+
+// subscription that resubscribes the same entries whenever the stream is lost
+pub struct ManagedSubscription {
+    events: mpsc::Receiver<SubscriptionEvent>,
+    task: JoinHandle<()>,
+}
+
+impl ManagedSubscription {
+    pub(crate) fn spawn(
+        client: KuksaClient,
+        entries: Vec<SubscribeEntry>,
+        options: SubscriptionOptions,
+    ) -> Self {
+        let (sender, events) = mpsc::channel(64);
+        let task = tokio::spawn(async move {
+            run(client, entries, options, sender).await;
+        });
+
+        ManagedSubscription { events, task }
+    }
+
+    // None when the subscription failed for good
+    pub async fn next_event(&mut self) -> Option<SubscriptionEvent> {
+        self.events.recv().await
+    }
+}
+
+impl Stream for ManagedSubscription {
+    type Item = SubscriptionEvent;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.events.poll_recv(cx)
+    }
+}
+
+impl Drop for ManagedSubscription {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+// returns None once the consumer is gone or the subscription failed
+async fn run(
+    client: KuksaClient,
+    entries: Vec<SubscribeEntry>,
+    options: SubscriptionOptions,
+    sender: mpsc::Sender<SubscriptionEvent>,
+) -> Option<()> {
+    // set while the stream is lost
+    let mut interrupted: Option<Instant> = None;
+    let mut attempt = 0;
+
+    loop {
+        let mut stream = match client.subscribe(entries.clone()).await {
+            Ok(stream) => stream,
+            Err(error) if error.is_retryable() => {
+                attempt += 1;
+                match options.backoff.delay(attempt) {
+                    Some(delay) => {
+                        tokio::time::sleep(delay).await;
+                        continue;
+                    }
+                    None => {
+                        let _ = sender.send(SubscriptionEvent::Failed(error)).await;
+                        return None;
+                    }
+                }
+            }
+            Err(error) => {
+                let _ = sender.send(SubscriptionEvent::Failed(error)).await;
+                return None;
+            }
+        };
+        attempt = 0;
+
+        if let Some(since) = interrupted.take() {
+            let gap = since.elapsed();
+            sender.send(SubscriptionEvent::Resumed { gap }).await.ok()?;
+
+            if options.replay_latest {
+                if let Ok(updates) = latest(&client, &entries).await {
+                    let response = SubscribeResponse { updates };
+                    sender
+                        .send(SubscriptionEvent::Update(response))
+                        .await
+                        .ok()?;
+                }
+            }
+        }
+
+        let error = loop {
+            match stream.next().await {
+                Some(Ok(response)) => {
+                    sender
+                        .send(SubscriptionEvent::Update(response))
+                        .await
+                        .ok()?;
+                }
+                Some(Err(status)) => break ClientError::from(status),
+                None => break ClientError::Connection("Subscription stream closed".to_string()),
+            }
+        };
+
+        interrupted = Some(Instant::now());
+        sender
+            .send(SubscriptionEvent::Interrupted(error))
+            .await
+            .ok()?;
+    }
+}
+
+// current values of the subscribed entries, in the shape of subscription updates
+async fn latest(
+    client: &KuksaClient,
+    entries: &[SubscribeEntry],
+) -> Result<Vec<EntryUpdate>, ClientError> {
+    let mut updates = vec![];
+
+    for entry in entries {
+        let data_entries = client
+            .get(&entry.path, entry.view, entry.fields.clone())
+            .await?;
+
+        for data_entry in data_entries {
+            updates.push(EntryUpdate {
+                entry: Some(data_entry),
+                fields: entry.fields.clone(),
+            });
+        }
+    }
+
+    Ok(updates)
+}
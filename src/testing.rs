@@ -0,0 +1,867 @@
+// in-memory databroker serving kuksa.val.v1, for the tests of apps built on KuksaClient, eg:
+//   let databroker = MockDatabroker::new().with_vss_json(include_str!("vss.json"))?;
+//   let server = databroker.serve()?;
+//   let client = server.client();
+// enabled by the "testing" feature
+use std::collections::{BTreeMap, HashMap};
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+
+use tokio::net::TcpListener;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tokio_stream::{Stream, StreamExt};
+use tonic::metadata::MetadataMap;
+use tonic::transport::server::Router;
+use tonic::transport::{Server, ServerTlsConfig};
+use tonic::{Request, Response, Status, Streaming};
+
+use databroker_proto::kuksa::val::v1::val_server::{Val, ValServer};
+use databroker_proto::kuksa::val::v1::value_restriction::Type as Restriction;
+use databroker_proto::kuksa::val::v1::{
+    DataEntry, DataEntryError, Datapoint, EntryType, EntryUpdate, Error, Field, GetRequest,
+    GetResponse, GetServerInfoRequest, GetServerInfoResponse, Metadata, SetRequest, SetResponse,
+    StreamedUpdateRequest, StreamedUpdateResponse, SubscribeRequest, SubscribeResponse,
+    ValueRestriction, View,
+};
+
+use crate::common::{value_matches_datatype, ClientError, DataType, Value};
+use crate::vss;
+use crate::KuksaClient;
+
+// the VAL methods errors can be injected into
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Method {
+    Get,
+    Set,
+    Subscribe,
+    StreamedUpdate,
+    GetServerInfo,
+}
+
+#[derive(Clone)]
+struct Failure {
+    status: Status,
+    // None: every call fails
+    remaining: Option<usize>,
+}
+
+// handed back for a writable auth-token, and accepted instead of it afterwards
+const AUTH_UUID: &str = "a2b1c3d4-0000-4000-8000-000000000001";
+
+struct State {
+    entries: BTreeMap<String, DataEntry>,
+    failures: HashMap<Method, Failure>,
+    latency: Duration,
+    version: String,
+    // accepted auth-tokens, with whether they allow writes; none: no authorization
+    tokens: HashMap<String, bool>,
+    // number of calls per method, and of calls authorized by auth-token (not by auth-uuid)
+    calls: HashMap<Method, usize>,
+    token_calls: usize,
+}
+
+// cheap to clone, the clones share the signals, so a test can change them while serving
+#[derive(Clone)]
+pub struct MockDatabroker {
+    state: Arc<Mutex<State>>,
+    // the entries changed by one set, for the subscribers
+    changes: broadcast::Sender<Vec<DataEntry>>,
+    // ends every open subscription with the status
+    closing: broadcast::Sender<Status>,
+}
+
+impl Default for MockDatabroker {
+    fn default() -> Self {
+        MockDatabroker::new()
+    }
+}
+
+impl MockDatabroker {
+    pub fn new() -> Self {
+        MockDatabroker {
+            state: Arc::new(Mutex::new(State {
+                entries: BTreeMap::new(),
+                failures: HashMap::new(),
+                latency: Duration::ZERO,
+                version: "0.4.4".to_string(),
+                tokens: HashMap::new(),
+                calls: HashMap::new(),
+                token_calls: 0,
+            })),
+            changes: broadcast::channel(256).0,
+            closing: broadcast::channel(16).0,
+        }
+    }
+
+    // every leaf of a VSS JSON spec, as the databroker loads it with --metadata
+    pub fn with_vss_json(self, spec: &str) -> Result<Self, ClientError> {
+        for (path, metadata) in vss::metadata(spec)? {
+            self.insert(&path, metadata);
+        }
+        Ok(self)
+    }
+
+    pub fn with_metadata(self, path: &str, metadata: Metadata) -> Self {
+        self.insert(path, metadata);
+        self
+    }
+
+    pub fn with_signal(self, path: &str, data_type: DataType, entry_type: EntryType) -> Self {
+        self.with_metadata(
+            path,
+            Metadata {
+                data_type: data_type.into(),
+                entry_type: entry_type.into(),
+                ..Default::default()
+            },
+        )
+    }
+
+    // min/max/allowed values of a signal added before, enforced on set like the databroker does
+    pub fn with_restriction(self, path: &str, restriction: Restriction) -> Self {
+        if let Some(entry) = self.state().entries.get_mut(path) {
+            if let Some(ref mut metadata) = entry.metadata {
+                metadata.value_restriction = Some(ValueRestriction {
+                    r#type: Some(restriction),
+                });
+            }
+        }
+        self
+    }
+
+    // accept the auth-token, a read-only token can not set values;
+    // once a token is added, calls without a valid token fail with Unauthenticated
+    pub fn with_token(self, token: &str, writable: bool) -> Self {
+        self.state().tokens.insert(token.to_string(), writable);
+        self
+    }
+
+    // current value of a signal added before
+    pub fn with_value(self, path: &str, value: Value) -> Self {
+        self.set_value(path, value);
+        self
+    }
+
+    // reported by GetServerInfo, "0.4.4" by default
+    pub fn with_version(self, version: &str) -> Self {
+        self.state().version = version.to_string();
+        self
+    }
+
+    // delay before every reply
+    pub fn with_latency(self, latency: Duration) -> Self {
+        self.set_latency(latency);
+        self
+    }
+
+    pub fn set_latency(&self, latency: Duration) {
+        self.state().latency = latency;
+    }
+
+    // set the current value like a feeder would and notify the subscribers,
+    // false if the path is unknown
+    pub fn set_value(&self, path: &str, value: Value) -> bool {
+        let mut state = self.state();
+        let Some(entry) = state.entries.get_mut(path) else {
+            return false;
+        };
+        entry.value = Some(Datapoint {
+            timestamp: Some(SystemTime::now().into()),
+            value: Some(value),
+        });
+        let _ = self.changes.send(vec![entry.clone()]);
+        true
+    }
+
+    pub fn value(&self, path: &str) -> Option<Value> {
+        let state = self.state();
+        let datapoint = state.entries.get(path)?.value.as_ref()?;
+        datapoint.value.clone()
+    }
+
+    pub fn target(&self, path: &str) -> Option<Value> {
+        let state = self.state();
+        let datapoint = state.entries.get(path)?.actuator_target.as_ref()?;
+        datapoint.value.clone()
+    }
+
+    // number of calls of the method so far, including the failed ones
+    pub fn calls(&self, method: Method) -> usize {
+        self.state().calls.get(&method).copied().unwrap_or(0)
+    }
+
+    // number of calls authorized by auth-token, the others used the auth-uuid
+    pub fn token_calls(&self) -> usize {
+        self.state().token_calls
+    }
+
+    // the next call of the method fails with the status
+    pub fn fail_next(&self, method: Method, status: Status) {
+        self.fail(method, status, Some(1));
+    }
+
+    // the next calls of the method fail with the status, eg: the ones in flight when it restarts
+    pub fn fail_times(&self, method: Method, status: Status, times: usize) {
+        if times > 0 {
+            self.fail(method, status, Some(times));
+        }
+    }
+
+    // every call of the method fails with the status, until clear_failures
+    pub fn fail_always(&self, method: Method, status: Status) {
+        self.fail(method, status, None);
+    }
+
+    pub fn clear_failures(&self) {
+        self.state().failures.clear();
+    }
+
+    // end every open subscription stream with the status, eg: Status::unavailable
+    pub fn close_subscriptions(&self, status: Status) {
+        let _ = self.closing.send(status);
+    }
+
+    // number of open subscription streams
+    pub fn subscriber_count(&self) -> usize {
+        self.closing.receiver_count()
+    }
+
+    // wait until a subscription stream is open
+    pub async fn wait_for_subscriber(&self) {
+        while self.subscriber_count() == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+
+    // serve on an ephemeral localhost port, on a runtime of its own:
+    // stopping the server drops every open connection, like a databroker restart
+    pub fn serve(&self) -> Result<MockServer, ClientError> {
+        self.spawn(SocketAddr::from(([127, 0, 0, 1], 0)), None)
+    }
+
+    // serve on the address, eg: the one of a stopped MockServer to restart the databroker
+    pub fn serve_on(&self, address: SocketAddr) -> Result<MockServer, ClientError> {
+        self.spawn(address, None)
+    }
+
+    // serve over TLS on an ephemeral localhost port, the url keeps the "http://" scheme
+    // as KuksaClient::with_tls upgrades it
+    pub fn serve_tls(&self, tls: ServerTlsConfig) -> Result<MockServer, ClientError> {
+        self.spawn(SocketAddr::from(([127, 0, 0, 1], 0)), Some(tls))
+    }
+
+    fn spawn(
+        &self,
+        address: SocketAddr,
+        tls: Option<ServerTlsConfig>,
+    ) -> Result<MockServer, ClientError> {
+        let service = ValServer::new(self.clone());
+        MockServer::start(address, tls, move |builder| builder.add_service(service))
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+
+    fn insert(&self, path: &str, metadata: Metadata) {
+        self.state().entries.insert(
+            path.to_string(),
+            DataEntry {
+                path: path.to_string(),
+                value: None,
+                actuator_target: None,
+                metadata: Some(metadata),
+            },
+        );
+    }
+
+    fn fail(&self, method: Method, status: Status, remaining: Option<usize>) {
+        self.state()
+            .failures
+            .insert(method, Failure { status, remaining });
+    }
+
+    // check the credentials, returns the auth-uuid to hand back to the client
+    #[allow(clippy::result_large_err)]
+    fn authorize(&self, metadata: &MetadataMap, write: bool) -> Result<Option<&str>, Status> {
+        let mut state = self.state();
+        if state.tokens.is_empty() {
+            return Ok(None);
+        }
+
+        // the auth-uuid is only issued for writable tokens
+        if let Some(uuid) = metadata.get("auth-uuid") {
+            return match uuid.to_str() {
+                Ok(AUTH_UUID) => Ok(None),
+                _ => Err(Status::unauthenticated("Invalid auth-uuid")),
+            };
+        }
+
+        let token = metadata
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .ok_or_else(|| Status::unauthenticated("Missing authorization"))?;
+
+        match state.tokens.get(token).copied() {
+            None => Err(Status::unauthenticated("Invalid auth-token")),
+            Some(false) if write => Err(Status::permission_denied("Read only token")),
+            Some(writable) => {
+                state.token_calls += 1;
+                Ok(writable.then_some(AUTH_UUID))
+            }
+        }
+    }
+
+    // wait the latency, then fail if an error was injected
+    async fn call(&self, method: Method) -> Result<(), Status> {
+        let (latency, failure) = {
+            let mut state = self.state();
+            *state.calls.entry(method).or_default() += 1;
+            let failure = match state.failures.get_mut(&method) {
+                Some(Failure {
+                    status,
+                    remaining: Some(remaining),
+                }) => {
+                    *remaining -= 1;
+                    let status = status.clone();
+                    if *remaining == 0 {
+                        state.failures.remove(&method);
+                    }
+                    Some(status)
+                }
+                Some(failure) => Some(failure.status.clone()),
+                None => None,
+            };
+            (state.latency, failure)
+        };
+
+        if !latency.is_zero() {
+            tokio::time::sleep(latency).await;
+        }
+        match failure {
+            Some(status) => Err(status),
+            None => Ok(()),
+        }
+    }
+
+    // validate every update first, nothing is written if one fails; the reasons and messages
+    // are the ones of the databroker
+    #[allow(clippy::result_large_err)]
+    fn apply(&self, updates: Vec<EntryUpdate>) -> Result<Vec<DataEntryError>, Status> {
+        let mut state = self.state();
+        let mut errors = vec![];
+        let mut writes = vec![];
+
+        for update in updates {
+            let Some(data_entry) = update.entry else {
+                continue;
+            };
+            let path = data_entry.path.clone();
+            let Some(entry) = state.entries.get(&path) else {
+                let message = format!("no datapoint registered for path {}", path);
+                errors.push(entry_error(path, 404, "not found", &message));
+                continue;
+            };
+            let metadata = entry.metadata.clone().unwrap_or_default();
+
+            let fields: Vec<Field> = update
+                .fields
+                .iter()
+                .filter_map(|field| Field::try_from(*field).ok())
+                .collect();
+            let target = fields.contains(&Field::ActuatorTarget);
+            // the whole call fails, not the entry
+            if target && metadata.entry_type != EntryType::Actuator as i32 {
+                return Err(Status::invalid_argument(
+                    "Tried to set a target value for a non-actuator. Non-actuators have no target value.",
+                ));
+            }
+
+            let datapoint = match target {
+                true => data_entry.actuator_target,
+                false => data_entry.value,
+            };
+            match check(&metadata, datapoint.as_ref()) {
+                Ok(()) => writes.push((path, target, datapoint)),
+                Err(error) => errors.push(DataEntryError {
+                    path,
+                    error: Some(error),
+                }),
+            }
+        }
+
+        if !errors.is_empty() {
+            return Ok(errors);
+        }
+
+        let mut changed = vec![];
+        for (path, target, datapoint) in writes {
+            let Some(entry) = state.entries.get_mut(&path) else {
+                continue;
+            };
+            let datapoint = datapoint.map(|datapoint| Datapoint {
+                timestamp: datapoint
+                    .timestamp
+                    .or_else(|| Some(SystemTime::now().into())),
+                value: datapoint.value,
+            });
+            match target {
+                true => entry.actuator_target = datapoint,
+                false => entry.value = datapoint,
+            }
+            changed.push(entry.clone());
+        }
+        if !changed.is_empty() {
+            let _ = self.changes.send(changed);
+        }
+
+        Ok(errors)
+    }
+}
+
+// the datatype and ValueRestriction checks of the databroker
+fn check(metadata: &Metadata, datapoint: Option<&Datapoint>) -> Result<(), Error> {
+    let Some(value) = datapoint.and_then(|datapoint| datapoint.value.as_ref()) else {
+        return Ok(());
+    };
+
+    let data_type = DataType::try_from(metadata.data_type).unwrap_or(DataType::Unspecified);
+    if !value_matches_datatype(value, data_type) {
+        return Err(Error {
+            code: 400,
+            reason: "type mismatch".to_string(),
+            message: "cannot set existing datapoint to value of different type".to_string(),
+        });
+    }
+
+    let restriction = metadata
+        .value_restriction
+        .as_ref()
+        .and_then(|restriction| restriction.r#type.as_ref());
+    match restriction {
+        Some(restriction) if !in_bounds(value, restriction) => Err(Error {
+            code: 400,
+            reason: "value out of bounds".to_string(),
+            message: "given value exceeds type's boundaries".to_string(),
+        }),
+        _ => Ok(()),
+    }
+}
+
+// min/max/allowed values of the restriction, kept apart from the client-side validation
+// (common::restriction_violation) so the tests can tell the two apart
+fn in_bounds(value: &Value, restriction: &Restriction) -> bool {
+    fn within<T: PartialOrd>(value: T, min: Option<T>, max: Option<T>, allowed: &[T]) -> bool {
+        min.is_none_or(|min| value >= min)
+            && max.is_none_or(|max| value <= max)
+            && (allowed.is_empty() || allowed.contains(&value))
+    }
+
+    match (value, restriction) {
+        (Value::String(value), Restriction::String(r)) => {
+            r.allowed_values.is_empty() || r.allowed_values.contains(value)
+        }
+        (Value::StringArray(array), Restriction::String(r)) => array
+            .values
+            .iter()
+            .all(|value| r.allowed_values.is_empty() || r.allowed_values.contains(value)),
+        (_, Restriction::Signed(r)) => {
+            let allowed: Vec<i128> = r.allowed_values.iter().map(|v| i128::from(*v)).collect();
+            let (min, max) = (r.min.map(i128::from), r.max.map(i128::from));
+            integers(value).all(|value| within(value, min, max, &allowed))
+        }
+        (_, Restriction::Unsigned(r)) => {
+            let allowed: Vec<i128> = r.allowed_values.iter().map(|v| i128::from(*v)).collect();
+            let (min, max) = (r.min.map(i128::from), r.max.map(i128::from));
+            integers(value).all(|value| within(value, min, max, &allowed))
+        }
+        // float signals are compared as f32, like the databroker stores their bounds
+        (Value::Float(_) | Value::FloatArray(_), Restriction::FloatingPoint(r)) => {
+            let allowed: Vec<f32> = r.allowed_values.iter().map(|v| *v as f32).collect();
+            let (min, max) = (r.min.map(|v| v as f32), r.max.map(|v| v as f32));
+            floats(value).all(|value| within(value as f32, min, max, &allowed))
+        }
+        (_, Restriction::FloatingPoint(r)) => {
+            floats(value).all(|value| within(value, r.min, r.max, &r.allowed_values))
+        }
+        _ => true,
+    }
+}
+
+fn integers(value: &Value) -> Box<dyn Iterator<Item = i128> + '_> {
+    match value {
+        Value::Int32(value) => Box::new(std::iter::once(i128::from(*value))),
+        Value::Int64(value) => Box::new(std::iter::once(i128::from(*value))),
+        Value::Uint32(value) => Box::new(std::iter::once(i128::from(*value))),
+        Value::Uint64(value) => Box::new(std::iter::once(i128::from(*value))),
+        Value::Int32Array(array) => Box::new(array.values.iter().map(|v| i128::from(*v))),
+        Value::Int64Array(array) => Box::new(array.values.iter().map(|v| i128::from(*v))),
+        Value::Uint32Array(array) => Box::new(array.values.iter().map(|v| i128::from(*v))),
+        Value::Uint64Array(array) => Box::new(array.values.iter().map(|v| i128::from(*v))),
+        _ => Box::new(std::iter::empty()),
+    }
+}
+
+fn floats(value: &Value) -> Box<dyn Iterator<Item = f64> + '_> {
+    match value {
+        Value::Float(value) => Box::new(std::iter::once(f64::from(*value))),
+        Value::Double(value) => Box::new(std::iter::once(*value)),
+        Value::FloatArray(array) => Box::new(array.values.iter().map(|v| f64::from(*v))),
+        Value::DoubleArray(array) => Box::new(array.values.iter().copied()),
+        _ => Box::new(std::iter::empty()),
+    }
+}
+
+fn entry_error(path: String, code: u32, reason: &str, message: &str) -> DataEntryError {
+    DataEntryError {
+        path,
+        error: Some(Error {
+            code,
+            reason: reason.to_string(),
+            message: message.to_string(),
+        }),
+    }
+}
+
+// exact path, a branch ("Vehicle.Cabin") or a wildcard ("Vehicle.*", "Vehicle.Cabin.*.IsOpen")
+fn matches(pattern: &str, path: &str) -> bool {
+    if pattern == path || pattern == "*" {
+        return true;
+    }
+    if !pattern.contains('*') {
+        return path.starts_with(pattern) && path[pattern.len()..].starts_with('.');
+    }
+
+    // "*" matches one segment, a trailing ".*" every leaf below
+    let patterns: Vec<&str> = pattern.split('.').collect();
+    let segments: Vec<&str> = path.split('.').collect();
+    if patterns.last() == Some(&"*") && segments.len() >= patterns.len() {
+        let prefix = &patterns[..patterns.len() - 1];
+        return prefix
+            .iter()
+            .zip(&segments)
+            .all(|(pattern, segment)| *pattern == "*" || pattern == segment);
+    }
+    patterns.len() == segments.len()
+        && patterns
+            .iter()
+            .zip(&segments)
+            .all(|(pattern, segment)| *pattern == "*" || pattern == segment)
+}
+
+// the entry with only the requested fields, the view decides when no field is given
+fn select(entry: &DataEntry, view: i32, fields: &[i32]) -> DataEntry {
+    let mut fields: Vec<Field> = fields
+        .iter()
+        .filter_map(|field| Field::try_from(*field).ok())
+        .collect();
+    if fields.is_empty() {
+        fields = match View::try_from(view).unwrap_or(View::CurrentValue) {
+            View::TargetValue => vec![Field::ActuatorTarget],
+            View::Metadata => vec![Field::Metadata],
+            View::All => vec![Field::Unspecified],
+            _ => vec![Field::Value],
+        };
+    }
+
+    let all = fields.contains(&Field::Unspecified);
+    let metadata = all
+        || fields
+            .iter()
+            .any(|field| *field as i32 >= Field::Metadata as i32);
+    DataEntry {
+        path: entry.path.clone(),
+        value: match all || fields.contains(&Field::Value) {
+            true => entry.value.clone(),
+            false => None,
+        },
+        actuator_target: match all || fields.contains(&Field::ActuatorTarget) {
+            true => entry.actuator_target.clone(),
+            false => None,
+        },
+        metadata: match metadata {
+            true => entry.metadata.clone(),
+            false => None,
+        },
+    }
+}
+
+#[tonic::async_trait]
+impl Val for MockDatabroker {
+    async fn get(&self, request: Request<GetRequest>) -> Result<Response<GetResponse>, Status> {
+        let auth_uuid = self.authorize(request.metadata(), false)?;
+        self.call(Method::Get).await?;
+        let state = self.state();
+        let mut response = GetResponse::default();
+
+        for entry_request in request.into_inner().entries {
+            let matched: Vec<DataEntry> = state
+                .entries
+                .values()
+                .filter(|entry| matches(&entry_request.path, &entry.path))
+                .map(|entry| select(entry, entry_request.view, &entry_request.fields))
+                .collect();
+
+            if matched.is_empty() {
+                let error = entry_error(
+                    entry_request.path,
+                    404,
+                    "not_found",
+                    "No entries found for the provided path",
+                );
+                response.errors.push(error);
+            }
+            response.entries.extend(matched);
+        }
+
+        Ok(with_auth_uuid(Response::new(response), auth_uuid))
+    }
+
+    async fn set(&self, request: Request<SetRequest>) -> Result<Response<SetResponse>, Status> {
+        let auth_uuid = self.authorize(request.metadata(), true)?;
+        self.call(Method::Set).await?;
+        let errors = self.apply(request.into_inner().updates)?;
+
+        Ok(with_auth_uuid(
+            Response::new(SetResponse {
+                error: None,
+                errors,
+            }),
+            auth_uuid,
+        ))
+    }
+
+    type StreamedUpdateStream =
+        Pin<Box<dyn Stream<Item = Result<StreamedUpdateResponse, Status>> + Send>>;
+
+    async fn streamed_update(
+        &self,
+        request: Request<Streaming<StreamedUpdateRequest>>,
+    ) -> Result<Response<Self::StreamedUpdateStream>, Status> {
+        self.authorize(request.metadata(), true)?;
+        self.call(Method::StreamedUpdate).await?;
+        let databroker = self.clone();
+
+        // like the databroker, only batches with errors are answered
+        let stream = request
+            .into_inner()
+            .filter_map(move |request| match request {
+                Ok(request) => match databroker.apply(request.updates) {
+                    Ok(errors) => (!errors.is_empty()).then_some(Ok(StreamedUpdateResponse {
+                        error: None,
+                        errors,
+                    })),
+                    Err(status) => Some(Err(status)),
+                },
+                Err(status) => Some(Err(status)),
+            });
+
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    type SubscribeStream = Pin<Box<dyn Stream<Item = Result<SubscribeResponse, Status>> + Send>>;
+
+    async fn subscribe(
+        &self,
+        request: Request<SubscribeRequest>,
+    ) -> Result<Response<Self::SubscribeStream>, Status> {
+        self.authorize(request.metadata(), false)?;
+        self.call(Method::Subscribe).await?;
+        let subscribed = request.into_inner().entries;
+
+        // the subscriber gets the current values first
+        let (initial, mut changes, mut closing) = {
+            let state = self.state();
+            let mut initial = vec![];
+            for subscribe_entry in &subscribed {
+                let matched: Vec<&DataEntry> = state
+                    .entries
+                    .values()
+                    .filter(|entry| matches(&subscribe_entry.path, &entry.path))
+                    .collect();
+                if matched.is_empty() {
+                    return Err(Status::not_found(format!(
+                        "{} not found",
+                        subscribe_entry.path
+                    )));
+                }
+                initial.extend(matched.into_iter().cloned());
+            }
+            (initial, self.changes.subscribe(), self.closing.subscribe())
+        };
+
+        // the subscribed fields of the changed entries
+        let updates = move |entries: Vec<DataEntry>| -> Vec<EntryUpdate> {
+            entries
+                .iter()
+                .filter_map(|entry| {
+                    let subscribe_entry = subscribed
+                        .iter()
+                        .find(|subscribe_entry| matches(&subscribe_entry.path, &entry.path))?;
+                    Some(EntryUpdate {
+                        entry: Some(select(entry, subscribe_entry.view, &subscribe_entry.fields)),
+                        fields: subscribe_entry.fields.clone(),
+                    })
+                })
+                .collect()
+        };
+
+        let (sender, receiver) = mpsc::channel(64);
+        tokio::spawn(async move {
+            let response = SubscribeResponse {
+                updates: updates(initial),
+            };
+            if sender.send(Ok(response)).await.is_err() {
+                return;
+            }
+
+            loop {
+                let changed = tokio::select! {
+                    changed = changes.recv() => changed,
+                    status = closing.recv() => {
+                        if let Ok(status) = status {
+                            let _ = sender.send(Err(status)).await;
+                        }
+                        return;
+                    }
+                    _ = sender.closed() => return,
+                };
+
+                let updates = match changed {
+                    Ok(changed) => updates(changed),
+                    Err(RecvError::Lagged(_)) => continue,
+                    Err(RecvError::Closed) => return,
+                };
+                if updates.is_empty() {
+                    continue;
+                }
+                if sender
+                    .send(Ok(SubscribeResponse { updates }))
+                    .await
+                    .is_err()
+                {
+                    return;
+                }
+            }
+        });
+
+        Ok(Response::new(Box::pin(ReceiverStream::new(receiver))))
+    }
+
+    async fn get_server_info(
+        &self,
+        _request: Request<GetServerInfoRequest>,
+    ) -> Result<Response<GetServerInfoResponse>, Status> {
+        self.call(Method::GetServerInfo).await?;
+
+        Ok(Response::new(GetServerInfoResponse {
+            name: "databroker".to_string(),
+            version: self.state().version.clone(),
+        }))
+    }
+}
+
+fn with_auth_uuid<T>(mut response: Response<T>, auth_uuid: Option<&str>) -> Response<T> {
+    if let Some(uuid) = auth_uuid.and_then(|uuid| uuid.parse().ok()) {
+        response.metadata_mut().insert("auth-uuid", uuid);
+    }
+    response
+}
+
+// a MockDatabroker being served, stopped when dropped
+pub struct MockServer {
+    address: SocketAddr,
+    shutdown: Option<oneshot::Sender<()>>,
+    thread: Option<std::thread::JoinHandle<()>>,
+}
+
+impl MockServer {
+    // serve the services added by route on a runtime of its own, eg: a stand-in for another
+    // databroker API; stopping the server drops every open connection
+    pub fn start<F>(
+        address: SocketAddr,
+        tls: Option<ServerTlsConfig>,
+        route: F,
+    ) -> Result<MockServer, ClientError>
+    where
+        F: FnOnce(&mut Server) -> Router,
+    {
+        let connection_error = |error: std::io::Error| ClientError::Connection(error.to_string());
+        let listener = std::net::TcpListener::bind(address).map_err(connection_error)?;
+        listener.set_nonblocking(true).map_err(connection_error)?;
+        let address = listener.local_addr().map_err(connection_error)?;
+
+        let mut builder = Server::builder();
+        if let Some(tls) = tls {
+            builder = builder
+                .tls_config(tls)
+                .map_err(|error| ClientError::Config(error.to_string()))?;
+        }
+        let router = route(&mut builder);
+        let (shutdown, signal) = oneshot::channel::<()>();
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .enable_all()
+            .build()
+            .map_err(connection_error)?;
+
+        let thread = std::thread::spawn(move || {
+            runtime.block_on(async move {
+                let Ok(listener) = TcpListener::from_std(listener) else {
+                    return;
+                };
+                let serve = router.serve_with_incoming(TcpListenerStream::new(listener));
+
+                tokio::select! {
+                    _ = serve => {}
+                    _ = signal => {}
+                }
+            });
+            runtime.shutdown_background();
+        });
+
+        Ok(MockServer {
+            address,
+            shutdown: Some(shutdown),
+            thread: Some(thread),
+        })
+    }
+
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+
+    // eg: http://127.0.0.1:40123
+    pub fn url(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    pub fn client(&self) -> KuksaClient {
+        KuksaClient::new(&self.url())
+    }
+
+    // stop serving and drop every open connection
+    pub fn stop(mut self) {
+        self.shutdown();
+    }
+
+    fn shutdown(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
@@ -0,0 +1,76 @@
+use std::path::Path;
+use tonic::transport::{Certificate, ClientTlsConfig, Identity};
+
+use crate::common::ClientError;
+
+// TLS settings used by KuksaClient when connecting to a databroker
+// eg: TlsConfig::new().ca_certificate(ca_pem).domain_name("Server")
+#[derive(Debug, Clone, Default)]
+pub struct TlsConfig {
+    ca_certificate: Option<Vec<u8>>,
+    identity: Option<(Vec<u8>, Vec<u8>)>,
+    domain_name: Option<String>,
+}
+
+impl TlsConfig {
+    pub fn new() -> Self {
+        TlsConfig::default()
+    }
+
+    // PEM encoded CA certificate used to verify the databroker
+    pub fn ca_certificate(mut self, pem: impl Into<Vec<u8>>) -> Self {
+        self.ca_certificate = Some(pem.into());
+        self
+    }
+
+    pub fn ca_certificate_file(self, path: impl AsRef<Path>) -> Result<Self, ClientError> {
+        let pem = read_pem(path.as_ref())?;
+        Ok(self.ca_certificate(pem))
+    }
+
+    // PEM encoded client certificate and private key, for mutual TLS
+    pub fn identity(mut self, cert_pem: impl Into<Vec<u8>>, key_pem: impl Into<Vec<u8>>) -> Self {
+        self.identity = Some((cert_pem.into(), key_pem.into()));
+        self
+    }
+
+    pub fn identity_files(
+        self,
+        cert_path: impl AsRef<Path>,
+        key_path: impl AsRef<Path>,
+    ) -> Result<Self, ClientError> {
+        let cert_pem = read_pem(cert_path.as_ref())?;
+        let key_pem = read_pem(key_path.as_ref())?;
+        Ok(self.identity(cert_pem, key_pem))
+    }
+
+    // override the name checked against the server certificate,
+    // eg: connect to 127.0.0.1 but verify the certificate issued for "Server"
+    pub fn domain_name(mut self, domain_name: &str) -> Self {
+        self.domain_name = Some(domain_name.to_string());
+        self
+    }
+
+    pub(crate) fn to_client_tls_config(&self) -> ClientTlsConfig {
+        let mut config = ClientTlsConfig::new();
+
+        if let Some(ca_certificate) = &self.ca_certificate {
+            config = config.ca_certificate(Certificate::from_pem(ca_certificate));
+        }
+
+        if let Some((cert_pem, key_pem)) = &self.identity {
+            config = config.identity(Identity::from_pem(cert_pem, key_pem));
+        }
+
+        if let Some(domain_name) = &self.domain_name {
+            config = config.domain_name(domain_name.clone());
+        }
+
+        config
+    }
+}
+
+fn read_pem(path: &Path) -> Result<Vec<u8>, ClientError> {
+    std::fs::read(path)
+        .map_err(|error| ClientError::Config(format!("Can not read {}: {}", path.display(), error)))
+}
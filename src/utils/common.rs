@@ -1,14 +1,224 @@
 use std::collections::HashMap;
+use std::fmt;
 
+use databroker_proto::kuksa::val::v1::value_restriction::Type as Restriction;
 pub use databroker_proto::kuksa::val::v1::{datapoint::Value, DataType, Datapoint, Error};
-use databroker_proto::kuksa::val::v1::{EntryType, Metadata};
+use databroker_proto::kuksa::val::v1::{
+    parse_array, EntryType, Int64Array, Metadata, ValueRestriction,
+};
+
+use crate::connection::is_connection_lost;
+use crate::timestamp::{parse_timestamp, system_time_to_nanos};
 
 #[derive(Debug, Clone)]
 pub enum ClientError {
+    // the databroker can not be reached
     Connection(String),
-    Status(tonic::Status),
+    // the client is misconfigured, eg: the address, TLS, auth-token or token file
+    Config(String),
+    // boxed, tonic::Status would make every Result<_, ClientError> large
+    Status(Box<tonic::Status>),
+    // the auth-token is missing, expired or was rejected (Unauthenticated)
+    Auth(Box<tonic::Status>),
+    // the databroker version does not support this client or the called method
+    Incompatible(String),
+    // errors returned by the databroker which have no variant of their own
     Function(Vec<Error>),
+    // errors keyed by path, from batch calls, eg: NotFound, TypeMismatch, OutOfRange
+    Entries(HashMap<String, ClientError>),
     Parse(String),
+    // the path is not a signal (sensor/actuator/attribute) known by the databroker
+    NotFound {
+        path: String,
+    },
+    // the target value of a sensor or attribute was set, read or subscribed
+    NotActuator {
+        path: String,
+    },
+    // the value does not match the datatype of the signal
+    TypeMismatch {
+        path: String,
+        message: String,
+    },
+    // the value violates the ValueRestriction (min/max/allowed values) of the signal
+    OutOfRange {
+        path: String,
+        message: String,
+    },
+    // the auth-token does not grant access to the path, None: to the called method
+    PermissionDenied {
+        path: Option<String>,
+        message: String,
+    },
+    // the databroker went away while the call was in flight
+    Unavailable(Box<tonic::Status>),
+    // the call, or the wait for a value, did not complete in time (or its subscription closed);
+    // last_value: the value seen last while waiting, eg: by KuksaClient::set_target_value_confirmed
+    Timeout {
+        path: Option<String>,
+        message: String,
+        last_value: Option<Value>,
+    },
+}
+
+impl ClientError {
+    // classify the error the databroker returned for an entry, eg: a DataEntryError of a SetResponse;
+    // by its HTTP-like code, the reason (eg: "value out of bounds") only tells the 400s apart
+    pub fn from_entry(path: &str, error: Error) -> Self {
+        let path = path.to_string();
+
+        match error.code {
+            404 => ClientError::NotFound { path },
+            403 => ClientError::PermissionDenied {
+                path: Some(path),
+                message: error.message,
+            },
+            401 => ClientError::Auth(Box::new(tonic::Status::unauthenticated(error.message))),
+            400 if error.reason.contains("bounds") || error.reason.contains("range") => {
+                ClientError::OutOfRange {
+                    path,
+                    message: error.message,
+                }
+            }
+            400 => ClientError::TypeMismatch {
+                path,
+                message: error.message,
+            },
+            _ => ClientError::Function(vec![error]),
+        }
+    }
+
+    // the path the error is about, if any
+    pub fn path(&self) -> Option<&str> {
+        match self {
+            ClientError::NotFound { path }
+            | ClientError::NotActuator { path }
+            | ClientError::TypeMismatch { path, .. }
+            | ClientError::OutOfRange { path, .. } => Some(path),
+            ClientError::PermissionDenied { path, .. } | ClientError::Timeout { path, .. } => {
+                path.as_deref()
+            }
+            _ => None,
+        }
+    }
+
+    // attach the path of a single-path call to an error raised for the whole call
+    pub(crate) fn with_path(self, path: &str) -> Self {
+        match self {
+            ClientError::PermissionDenied {
+                path: None,
+                message,
+            } => ClientError::PermissionDenied {
+                path: Some(path.to_string()),
+                message,
+            },
+            ClientError::Timeout {
+                path: None,
+                message,
+                last_value,
+            } => ClientError::Timeout {
+                path: Some(path.to_string()),
+                message,
+                last_value,
+            },
+            error => error,
+        }
+    }
+
+    // the same call may succeed later (once the databroker is back), the client reconnects
+    // and retries reads and subscriptions itself (a Timeout on the same channel), sets are left
+    // to the caller as they may have been applied already; the others need a change of the
+    // request or of the setup
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            ClientError::Connection(_) | ClientError::Unavailable(_) | ClientError::Timeout { .. }
+        )
+    }
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::Connection(message) => write!(f, "connection failed: {}", message),
+            ClientError::Config(message) => write!(f, "invalid configuration: {}", message),
+            ClientError::Status(status) => write!(
+                f,
+                "databroker returned {:?}: {}",
+                status.code(),
+                status.message()
+            ),
+            ClientError::Auth(status) => write!(f, "authentication failed: {}", status.message()),
+            ClientError::Incompatible(message) => {
+                write!(f, "incompatible databroker: {}", message)
+            }
+            ClientError::Function(errors) => {
+                let errors: Vec<String> = errors.iter().map(error_to_string).collect();
+                write!(f, "databroker returned errors: {}", errors.join("; "))
+            }
+            ClientError::Entries(errors) => {
+                let mut errors: Vec<String> = errors
+                    .iter()
+                    .map(|(path, error)| match error.path() {
+                        Some(_) => error.to_string(),
+                        None => format!("{}: {}", path, error),
+                    })
+                    .collect();
+                errors.sort();
+                write!(f, "{}", errors.join("; "))
+            }
+            ClientError::Parse(message) => write!(f, "{}", message),
+            ClientError::NotFound { path } => write!(f, "{}: no such signal", path),
+            ClientError::NotActuator { path } => write!(f, "{}: not an actuator", path),
+            ClientError::TypeMismatch { path, message }
+            | ClientError::OutOfRange { path, message } => write!(f, "{}: {}", path, message),
+            ClientError::PermissionDenied { path, message } => match path {
+                Some(path) => write!(f, "{}: permission denied: {}", path, message),
+                None => write!(f, "permission denied: {}", message),
+            },
+            ClientError::Unavailable(status) => {
+                write!(f, "databroker unavailable: {}", status.message())
+            }
+            ClientError::Timeout { path, message, .. } => match path {
+                Some(path) => write!(f, "{}: timed out: {}", path, message),
+                None => write!(f, "timed out: {}", message),
+            },
+        }
+    }
+}
+
+impl std::error::Error for ClientError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ClientError::Status(status)
+            | ClientError::Auth(status)
+            | ClientError::Unavailable(status) => Some(status.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+fn error_to_string(error: &Error) -> String {
+    format!("{} {}: {}", error.code, error.reason, error.message)
+}
+
+impl From<tonic::Status> for ClientError {
+    fn from(status: tonic::Status) -> Self {
+        match status.code() {
+            tonic::Code::Unauthenticated => ClientError::Auth(Box::new(status)),
+            tonic::Code::PermissionDenied => ClientError::PermissionDenied {
+                path: None,
+                message: status.message().to_string(),
+            },
+            tonic::Code::DeadlineExceeded => ClientError::Timeout {
+                path: None,
+                message: status.message().to_string(),
+                last_value: None,
+            },
+            _ if is_connection_lost(&status) => ClientError::Unavailable(Box::new(status)),
+            _ => ClientError::Status(Box::new(status)),
+        }
+    }
 }
 
 // convert a str to Value
@@ -61,30 +271,194 @@ pub fn str_to_value(input: &str, datatype: DataType) -> Result<Value, ClientErro
             Ok(value) => Ok(Value::Double(value)),
             Err(_err) => Err(ClientError::Parse("Parse Double error".to_string())),
         },
+        // eg: (Int8Array, "[1, -2, 3]") or (StringArray, "a, b") --> [...]
+        DataType::StringArray
+        | DataType::BooleanArray
+        | DataType::Int8Array
+        | DataType::Int16Array
+        | DataType::Int32Array
+        | DataType::Int64Array
+        | DataType::Uint8Array
+        | DataType::Uint16Array
+        | DataType::Uint32Array
+        | DataType::Uint64Array
+        | DataType::FloatArray
+        | DataType::DoubleArray => Value::new(datatype, input)
+            .map_err(|_err| ClientError::Parse(format!("Parse {:?} error", datatype))),
+        // eg: (Timestamp, "2024-05-01T12:00:00Z") --> 1714564800000000000 (nanoseconds)
+        DataType::Timestamp => {
+            parse_timestamp(input).map(|time| Value::Int64(system_time_to_nanos(time)))
+        }
+        DataType::TimestampArray => match parse_array(input) {
+            Ok(elements) => elements
+                .iter()
+                .map(|element| parse_timestamp(element).map(system_time_to_nanos))
+                .collect::<Result<Vec<i64>, ClientError>>()
+                .map(|values| Value::Int64Array(Int64Array { values }))
+                .map_err(|_err| ClientError::Parse("Parse TimestampArray error".to_string())),
+            Err(_err) => Err(ClientError::Parse("Parse TimestampArray error".to_string())),
+        },
         _ => Err(ClientError::Parse("Datatype is not supported".to_string())),
         // DataType::Unspecified => todo!(),
-        // DataType::Timestamp => todo!(),
-        // DataType::StringArray => todo!(),
-        // DataType::BooleanArray => todo!(),
-        // DataType::Int8Array => todo!(),
-        // DataType::Int16Array => todo!(),
-        // DataType::Int32Array => todo!(),
-        // DataType::Int64Array => todo!(),
-        // DataType::Uint8Array => todo!(),
-        // DataType::Uint16Array => todo!(),
-        // DataType::Uint32Array => todo!(),
-        // DataType::Uint64Array => todo!(),
-        // DataType::FloatArray => todo!(),
-        // DataType::DoubleArray => todo!(),
-        // DataType::TimestampArray => todo!(),
     }
 }
 
+// check a typed Value against the datatype of an entry
+// eg: (Int32(300), Int8) --> false, 300 is out of the int8 range
+pub fn value_matches_datatype(value: &Value, datatype: DataType) -> bool {
+    match (datatype, value) {
+        (DataType::String, Value::String(_)) => true,
+        (DataType::Boolean, Value::Bool(_)) => true,
+        (DataType::Int8, Value::Int32(value)) => i8::try_from(*value).is_ok(),
+        (DataType::Int16, Value::Int32(value)) => i16::try_from(*value).is_ok(),
+        (DataType::Int32, Value::Int32(_)) => true,
+        (DataType::Int64, Value::Int64(_)) => true,
+        (DataType::Uint8, Value::Uint32(value)) => u8::try_from(*value).is_ok(),
+        (DataType::Uint16, Value::Uint32(value)) => u16::try_from(*value).is_ok(),
+        (DataType::Uint32, Value::Uint32(_)) => true,
+        (DataType::Uint64, Value::Uint64(_)) => true,
+        (DataType::Float, Value::Float(_)) => true,
+        (DataType::Double, Value::Double(_)) => true,
+        (DataType::Timestamp, Value::Int64(_)) => true,
+        (DataType::StringArray, Value::StringArray(_)) => true,
+        (DataType::BooleanArray, Value::BoolArray(_)) => true,
+        (DataType::Int8Array, Value::Int32Array(array)) => array
+            .values
+            .iter()
+            .all(|value| i8::try_from(*value).is_ok()),
+        (DataType::Int16Array, Value::Int32Array(array)) => array
+            .values
+            .iter()
+            .all(|value| i16::try_from(*value).is_ok()),
+        (DataType::Int32Array, Value::Int32Array(_)) => true,
+        (DataType::Int64Array, Value::Int64Array(_)) => true,
+        (DataType::Uint8Array, Value::Uint32Array(array)) => array
+            .values
+            .iter()
+            .all(|value| u8::try_from(*value).is_ok()),
+        (DataType::Uint16Array, Value::Uint32Array(array)) => array
+            .values
+            .iter()
+            .all(|value| u16::try_from(*value).is_ok()),
+        (DataType::Uint32Array, Value::Uint32Array(_)) => true,
+        (DataType::Uint64Array, Value::Uint64Array(_)) => true,
+        (DataType::FloatArray, Value::FloatArray(_)) => true,
+        (DataType::DoubleArray, Value::DoubleArray(_)) => true,
+        (DataType::TimestampArray, Value::Int64Array(_)) => true,
+        _ => false,
+    }
+}
+
+// check a typed Value against the ValueRestriction of its metadata, arrays element by element
+// eg: (Uint32(120), max 100) --> Some("120 is above the maximum 100")
+pub fn restriction_violation(value: &Value, restriction: &ValueRestriction) -> Option<String> {
+    fn each<T: Copy>(values: &[T], check: impl Fn(T) -> Option<String>) -> Option<String> {
+        values
+            .iter()
+            .enumerate()
+            .find_map(|(i, value)| check(*value).map(|violation| format!("[{}]: {}", i, violation)))
+    }
+
+    match (value, restriction.r#type.as_ref()?) {
+        (Value::String(value), Restriction::String(restriction)) => {
+            check_allowed(value, &restriction.allowed_values)
+        }
+        (Value::StringArray(array), Restriction::String(restriction)) => {
+            array.values.iter().enumerate().find_map(|(i, value)| {
+                check_allowed(value, &restriction.allowed_values)
+                    .map(|violation| format!("[{}]: {}", i, violation))
+            })
+        }
+        (Value::Int32(value), Restriction::Signed(r)) => {
+            check_bounds(i64::from(*value), r.min, r.max, &r.allowed_values)
+        }
+        (Value::Int64(value), Restriction::Signed(r)) => {
+            check_bounds(*value, r.min, r.max, &r.allowed_values)
+        }
+        (Value::Int32Array(array), Restriction::Signed(r)) => each(&array.values, |value| {
+            check_bounds(i64::from(value), r.min, r.max, &r.allowed_values)
+        }),
+        (Value::Int64Array(array), Restriction::Signed(r)) => each(&array.values, |value| {
+            check_bounds(value, r.min, r.max, &r.allowed_values)
+        }),
+        (Value::Uint32(value), Restriction::Unsigned(r)) => {
+            check_bounds(u64::from(*value), r.min, r.max, &r.allowed_values)
+        }
+        (Value::Uint64(value), Restriction::Unsigned(r)) => {
+            check_bounds(*value, r.min, r.max, &r.allowed_values)
+        }
+        (Value::Uint32Array(array), Restriction::Unsigned(r)) => each(&array.values, |value| {
+            check_bounds(u64::from(value), r.min, r.max, &r.allowed_values)
+        }),
+        (Value::Uint64Array(array), Restriction::Unsigned(r)) => each(&array.values, |value| {
+            check_bounds(value, r.min, r.max, &r.allowed_values)
+        }),
+        // bounds are rounded to f32 for floats, eg: 0.1f32 is not above a max of 0.1
+        (Value::Float(value), Restriction::FloatingPoint(r)) => {
+            let (min, max, allowed) = as_f32(r.min, r.max, &r.allowed_values);
+            check_bounds(*value, min, max, &allowed)
+        }
+        (Value::FloatArray(array), Restriction::FloatingPoint(r)) => {
+            let (min, max, allowed) = as_f32(r.min, r.max, &r.allowed_values);
+            each(&array.values, |value| {
+                check_bounds(value, min, max, &allowed)
+            })
+        }
+        (Value::Double(value), Restriction::FloatingPoint(r)) => {
+            check_bounds(*value, r.min, r.max, &r.allowed_values)
+        }
+        (Value::DoubleArray(array), Restriction::FloatingPoint(r)) => {
+            each(&array.values, |value| {
+                check_bounds(value, r.min, r.max, &r.allowed_values)
+            })
+        }
+        // a restriction of another type does not apply
+        _ => None,
+    }
+}
+
+fn check_bounds<T: PartialOrd + std::fmt::Display>(
+    value: T,
+    min: Option<T>,
+    max: Option<T>,
+    allowed: &[T],
+) -> Option<String> {
+    match (min, max) {
+        (Some(min), _) if value < min => Some(format!("{} is below the minimum {}", value, min)),
+        (_, Some(max)) if value > max => Some(format!("{} is above the maximum {}", value, max)),
+        _ => check_allowed(&value, allowed),
+    }
+}
+
+fn check_allowed<T: PartialEq + std::fmt::Display>(value: &T, allowed: &[T]) -> Option<String> {
+    if allowed.is_empty() || allowed.contains(value) {
+        return None;
+    }
+    let allowed: Vec<String> = allowed.iter().map(ToString::to_string).collect();
+    Some(format!(
+        "{} is not one of the allowed values [{}]",
+        value,
+        allowed.join(", ")
+    ))
+}
+
+fn as_f32(
+    min: Option<f64>,
+    max: Option<f64>,
+    allowed: &[f64],
+) -> (Option<f32>, Option<f32>, Vec<f32>) {
+    (
+        min.map(|min| min as f32),
+        max.map(|max| max as f32),
+        allowed.iter().map(|allowed| *allowed as f32).collect(),
+    )
+}
+
 pub fn value_from_datapoint(datapoint: Option<Datapoint>) -> Option<Value> {
     if let Some(data) = datapoint {
         return data.value;
     }
-    return None;
+    None
 }
 
 pub async fn datatype_from_metadata(
@@ -96,7 +470,10 @@ pub async fn datatype_from_metadata(
         let datatype = match DataType::try_from(metadata.1.data_type) {
             Ok(datatype) => datatype,
             Err(_error) => {
-                return Err(ClientError::Function(vec![]));
+                return Err(ClientError::Parse(format!(
+                    "{}: unknown datatype {} in the metadata",
+                    metadata.0, metadata.1.data_type
+                )));
             }
         };
         result.insert(metadata.0.to_owned(), datatype);
@@ -115,7 +492,10 @@ pub async fn entrytype_from_metadata(
         let entrytype = match EntryType::try_from(metadata.1.entry_type) {
             Ok(entrytype) => entrytype,
             Err(_error) => {
-                return Err(ClientError::Function(vec![]));
+                return Err(ClientError::Parse(format!(
+                    "{}: unknown entry type {} in the metadata",
+                    metadata.0, metadata.1.entry_type
+                )));
             }
         };
         result.insert(metadata.0.to_owned(), entrytype);
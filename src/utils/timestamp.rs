@@ -0,0 +1,67 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use time::format_description::well_known::Rfc3339;
+use time::OffsetDateTime;
+
+use crate::common::ClientError;
+
+// the VAL API has no timestamp variant in Datapoint.value, so values of
+// Timestamp/TimestampArray signals are carried as Int64/Int64Array nanoseconds since the epoch
+
+// parse a point in time, eg: "2024-05-01T12:00:00Z", "2024-05-01T14:00:00.5+02:00"
+// or milliseconds since the epoch, eg: "1714564800000"
+pub fn parse_timestamp(input: &str) -> Result<SystemTime, ClientError> {
+    let input = input.trim();
+
+    if let Ok(millis) = input.parse::<i64>() {
+        return Ok(epoch_offset(i128::from(millis) * 1_000_000));
+    }
+
+    OffsetDateTime::parse(input, &Rfc3339)
+        .map(SystemTime::from)
+        .map_err(|_err| ClientError::Parse("Parse Timestamp error".to_string()))
+}
+
+// RFC 3339 in UTC, eg: "2024-05-01T12:00:00.5Z"
+pub fn format_timestamp(time: SystemTime) -> String {
+    OffsetDateTime::from(time)
+        .format(&Rfc3339)
+        .unwrap_or_else(|_err| format!("{:?}", time))
+}
+
+pub fn timestamp_to_system_time(
+    timestamp: &prost_types::Timestamp,
+) -> Result<SystemTime, ClientError> {
+    SystemTime::try_from(timestamp.clone())
+        .map_err(|err| ClientError::Parse(format!("Invalid timestamp: {}", err)))
+}
+
+// eg: Timestamp { seconds: 1714564800, nanos: 0 } --> "2024-05-01T12:00:00Z"
+pub fn timestamp_to_string(timestamp: &prost_types::Timestamp) -> String {
+    match timestamp_to_system_time(timestamp) {
+        Ok(time) => format_timestamp(time),
+        Err(_err) => timestamp.to_string(),
+    }
+}
+
+// nanoseconds since the epoch, saturating at the i64 range (years 1677 - 2262)
+pub fn system_time_to_nanos(time: SystemTime) -> i64 {
+    let nanos = match time.duration_since(UNIX_EPOCH) {
+        Ok(after) => after.as_nanos() as i128,
+        Err(before) => -(before.duration().as_nanos() as i128),
+    };
+    nanos.clamp(i64::MIN as i128, i64::MAX as i128) as i64
+}
+
+pub fn nanos_to_system_time(nanos: i64) -> SystemTime {
+    epoch_offset(i128::from(nanos))
+}
+
+fn epoch_offset(nanos: i128) -> SystemTime {
+    let offset = Duration::from_nanos(nanos.unsigned_abs().min(u64::MAX as u128) as u64);
+    if nanos >= 0 {
+        UNIX_EPOCH + offset
+    } else {
+        UNIX_EPOCH - offset
+    }
+}
@@ -0,0 +1,413 @@
+// VSS signals known at compile time, generated from the VSS JSON spec the databroker loads, eg:
+//   kuksa-cli codegen vss_release_3.0.json -o src/vss.rs
+// or from a build.rs:
+//   simple_kuksa_client::vss::generate_file("vss_release_3.0.json", out_dir.join("vss.rs"))?;
+// then:
+//   client.get_current_value(vss::vehicle::Speed.path).await?;
+//   let speed = vss::vehicle::Speed.signal(&client).await?; // Signal<f32>
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::marker::PhantomData;
+use std::path::Path;
+
+use serde_json::{Map, Value as Json};
+
+use databroker_proto::kuksa::val::v1::value_restriction::Type as Restriction;
+pub use databroker_proto::kuksa::val::v1::{DataType, EntryType};
+use databroker_proto::kuksa::val::v1::{
+    Metadata, ValueRestriction, ValueRestrictionFloat, ValueRestrictionInt, ValueRestrictionString,
+    ValueRestrictionUint,
+};
+
+use crate::common::ClientError;
+use crate::signal::{Signal, SignalValue};
+use crate::KuksaClient;
+
+// a leaf of the VSS tree, T is the rust type of its values (see SignalValue)
+pub struct VssSignal<T> {
+    pub path: &'static str,
+    pub data_type: DataType,
+    pub entry_type: EntryType,
+    pub unit: Option<&'static str>,
+    pub description: &'static str,
+    // empty if any value is allowed
+    pub allowed: &'static [&'static str],
+    value_type: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for VssSignal<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for VssSignal<T> {}
+
+impl<T> VssSignal<T> {
+    pub const fn new(
+        path: &'static str,
+        data_type: DataType,
+        entry_type: EntryType,
+        unit: Option<&'static str>,
+        description: &'static str,
+        allowed: &'static [&'static str],
+    ) -> Self {
+        VssSignal {
+            path,
+            data_type,
+            entry_type,
+            unit,
+            description,
+            allowed,
+            value_type: PhantomData,
+        }
+    }
+}
+
+impl<T: SignalValue> VssSignal<T> {
+    // typed handle, fails if the databroker serves another datatype than the spec
+    pub async fn signal(&self, client: &KuksaClient) -> Result<Signal<T>, ClientError> {
+        client.signal::<T>(self.path).await
+    }
+}
+
+// rust source with a module per branch and a VssSignal constant per leaf, eg:
+//   vehicle::cabin::seat::row1::pos1::Position: VssSignal<u16>
+pub fn generate(spec: &str) -> Result<String, ClientError> {
+    let spec = parse(spec)?;
+    let roots = roots(&spec)?;
+
+    let mut code =
+        String::from("// generated from a VSS JSON spec by simple-kuksa-client, do not edit\n");
+    for (name, node) in roots {
+        code.push('\n');
+        generate_node(&mut code, name, name, node, 0)?;
+    }
+    Ok(code)
+}
+
+// metadata of every leaf by path, as the databroker serves it after loading the spec,
+// including the ValueRestriction built from min/max/allowed
+pub fn metadata(spec: &str) -> Result<BTreeMap<String, Metadata>, ClientError> {
+    let spec = parse(spec)?;
+    let mut metadatas = BTreeMap::new();
+    for (name, node) in roots(&spec)? {
+        collect_metadata(&mut metadatas, name, node)?;
+    }
+    Ok(metadatas)
+}
+
+// generate from the spec file into the output file, eg: from a build.rs
+pub fn generate_file(spec: impl AsRef<Path>, output: impl AsRef<Path>) -> Result<(), ClientError> {
+    let (spec, output) = (spec.as_ref(), output.as_ref());
+    let json = std::fs::read_to_string(spec).map_err(|error| {
+        ClientError::Parse(format!("Can not read {}: {}", spec.display(), error))
+    })?;
+    let code = generate(&json)?;
+    std::fs::write(output, code).map_err(|error| {
+        ClientError::Parse(format!("Can not write {}: {}", output.display(), error))
+    })
+}
+
+fn parse(spec: &str) -> Result<Json, ClientError> {
+    serde_json::from_str(spec)
+        .map_err(|error| ClientError::Parse(format!("Invalid VSS JSON: {}", error)))
+}
+
+fn roots(spec: &Json) -> Result<&Map<String, Json>, ClientError> {
+    spec.as_object()
+        .ok_or_else(|| ClientError::Parse("Invalid VSS JSON: expected an object".to_string()))
+}
+
+fn collect_metadata(
+    metadatas: &mut BTreeMap<String, Metadata>,
+    path: &str,
+    node: &Json,
+) -> Result<(), ClientError> {
+    let node = node.as_object().ok_or_else(|| {
+        ClientError::Parse(format!("Invalid VSS JSON: {} is not an object", path))
+    })?;
+
+    let entry_type = match text(node, "type").unwrap_or("branch") {
+        "branch" => {
+            if let Some(children) = node.get("children").and_then(Json::as_object) {
+                for (child, node) in children {
+                    collect_metadata(metadatas, &format!("{}.{}", path, child), node)?;
+                }
+            }
+            return Ok(());
+        }
+        "sensor" => EntryType::Sensor,
+        "actuator" => EntryType::Actuator,
+        "attribute" => EntryType::Attribute,
+        _ => return Ok(()),
+    };
+    let datatype = text(node, "datatype")
+        .ok_or_else(|| ClientError::Parse(format!("Invalid VSS JSON: {} has no datatype", path)))?;
+    let Some((data_type, _)) = data_type(datatype) else {
+        return Ok(());
+    };
+
+    let optional = |key| text(node, key).map(str::to_string);
+    metadatas.insert(
+        path.to_string(),
+        Metadata {
+            data_type: data_type.into(),
+            entry_type: entry_type.into(),
+            description: optional("description"),
+            comment: optional("comment"),
+            deprecation: optional("deprecation"),
+            unit: optional("unit"),
+            value_restriction: value_restriction(node, datatype),
+            entry_specific: None,
+        },
+    );
+    Ok(())
+}
+
+// min/max/allowed of the leaf, typed by its datatype
+fn value_restriction(node: &Map<String, Json>, datatype: &str) -> Option<ValueRestriction> {
+    let bound = |key| node.get(key);
+    let allowed = node.get("allowed").and_then(Json::as_array);
+    if bound("min").is_none() && bound("max").is_none() && allowed.is_none() {
+        return None;
+    }
+    let allowed = allowed.map(Vec::as_slice).unwrap_or_default();
+
+    let restriction = match datatype.trim_end_matches("[]") {
+        "string" => Restriction::String(ValueRestrictionString {
+            allowed_values: allowed
+                .iter()
+                .filter_map(|value| value.as_str().map(str::to_string))
+                .collect(),
+        }),
+        "int8" | "int16" | "int32" | "int64" => Restriction::Signed(ValueRestrictionInt {
+            min: bound("min").and_then(Json::as_i64),
+            max: bound("max").and_then(Json::as_i64),
+            allowed_values: allowed.iter().filter_map(Json::as_i64).collect(),
+        }),
+        "uint8" | "uint16" | "uint32" | "uint64" => Restriction::Unsigned(ValueRestrictionUint {
+            min: bound("min").and_then(Json::as_u64),
+            max: bound("max").and_then(Json::as_u64),
+            allowed_values: allowed.iter().filter_map(Json::as_u64).collect(),
+        }),
+        "float" | "double" => Restriction::FloatingPoint(ValueRestrictionFloat {
+            min: bound("min").and_then(Json::as_f64),
+            max: bound("max").and_then(Json::as_f64),
+            allowed_values: allowed.iter().filter_map(Json::as_f64).collect(),
+        }),
+        _ => return None,
+    };
+
+    Some(ValueRestriction {
+        r#type: Some(restriction),
+    })
+}
+
+fn generate_node(
+    code: &mut String,
+    path: &str,
+    name: &str,
+    node: &Json,
+    depth: usize,
+) -> Result<(), ClientError> {
+    let indent = "    ".repeat(depth);
+    let node = node.as_object().ok_or_else(|| {
+        ClientError::Parse(format!("Invalid VSS JSON: {} is not an object", path))
+    })?;
+    let description = text(node, "description").unwrap_or_default();
+
+    match text(node, "type").unwrap_or("branch") {
+        "branch" => {
+            doc(code, &indent, description);
+            if depth == 0 {
+                // leaves keep their VSS names (eg: IsOpen), apps use a few of them
+                let _ = writeln!(code, "#[allow(dead_code, non_upper_case_globals)]");
+            }
+            let _ = writeln!(code, "{}pub mod {} {{", indent, module_name(name));
+            if let Some(children) = node.get("children").and_then(Json::as_object) {
+                for (child, node) in children {
+                    let child_path = format!("{}.{}", path, child);
+                    generate_node(code, &child_path, child, node, depth + 1)?;
+                }
+            }
+            let _ = writeln!(code, "{}}}", indent);
+        }
+        entry_type @ ("sensor" | "actuator" | "attribute") => {
+            let datatype = text(node, "datatype").ok_or_else(|| {
+                ClientError::Parse(format!("Invalid VSS JSON: {} has no datatype", path))
+            })?;
+            let Some((data_type, rust_type)) = data_type(datatype) else {
+                // struct datatypes can not be carried by the VAL API
+                let _ = writeln!(
+                    code,
+                    "{}// {}: unsupported datatype {}",
+                    indent, path, datatype
+                );
+                return Ok(());
+            };
+
+            let unit = match text(node, "unit") {
+                Some(unit) => format!("Some({:?})", unit),
+                None => "None".to_string(),
+            };
+            let allowed: Vec<String> = allowed(node)
+                .iter()
+                .map(|value| format!("{:?}", value))
+                .collect();
+
+            doc(code, &indent, description);
+            let _ = writeln!(
+                code,
+                "{}pub const {}: ::simple_kuksa_client::vss::VssSignal<{}> =",
+                indent,
+                identifier(name),
+                rust_type
+            );
+            let _ = writeln!(
+                code,
+                "{}    ::simple_kuksa_client::vss::VssSignal::new(",
+                indent
+            );
+            let _ = writeln!(code, "{}        {:?},", indent, path);
+            let _ = writeln!(
+                code,
+                "{}        ::simple_kuksa_client::vss::DataType::{:?},",
+                indent, data_type
+            );
+            let _ = writeln!(
+                code,
+                "{}        ::simple_kuksa_client::vss::EntryType::{},",
+                indent,
+                capitalize(entry_type)
+            );
+            let _ = writeln!(code, "{}        {},", indent, unit);
+            let _ = writeln!(code, "{}        {:?},", indent, description);
+            let _ = writeln!(code, "{}        &[{}],", indent, allowed.join(", "));
+            let _ = writeln!(code, "{}    );", indent);
+        }
+        // struct and property definitions have no signals
+        _ => {}
+    }
+
+    Ok(())
+}
+
+fn text<'a>(node: &'a Map<String, Json>, key: &str) -> Option<&'a str> {
+    node.get(key).and_then(Json::as_str)
+}
+
+// allowed values as strings, they may be numbers in the spec
+fn allowed(node: &Map<String, Json>) -> Vec<String> {
+    let Some(values) = node.get("allowed").and_then(Json::as_array) else {
+        return vec![];
+    };
+    values
+        .iter()
+        .map(|value| match value {
+            Json::String(value) => value.clone(),
+            value => value.to_string(),
+        })
+        .collect()
+}
+
+fn doc(code: &mut String, indent: &str, description: &str) {
+    for line in description.lines() {
+        let _ = writeln!(code, "{}/// {}", indent, line.trim_end());
+    }
+}
+
+// VSS datatype -> (DataType, rust type), eg: "uint8[]" -> (Uint8Array, "Vec<u8>")
+fn data_type(datatype: &str) -> Option<(DataType, String)> {
+    let (element, array) = match datatype.strip_suffix("[]") {
+        Some(element) => (element, true),
+        None => (datatype, false),
+    };
+    let (data_type, array_type, rust_type) = match element {
+        "boolean" => (DataType::Boolean, DataType::BooleanArray, "bool"),
+        "string" => (
+            DataType::String,
+            DataType::StringArray,
+            "::std::string::String",
+        ),
+        "int8" => (DataType::Int8, DataType::Int8Array, "i8"),
+        "int16" => (DataType::Int16, DataType::Int16Array, "i16"),
+        "int32" => (DataType::Int32, DataType::Int32Array, "i32"),
+        "int64" => (DataType::Int64, DataType::Int64Array, "i64"),
+        "uint8" => (DataType::Uint8, DataType::Uint8Array, "u8"),
+        "uint16" => (DataType::Uint16, DataType::Uint16Array, "u16"),
+        "uint32" => (DataType::Uint32, DataType::Uint32Array, "u32"),
+        "uint64" => (DataType::Uint64, DataType::Uint64Array, "u64"),
+        "float" => (DataType::Float, DataType::FloatArray, "f32"),
+        "double" => (DataType::Double, DataType::DoubleArray, "f64"),
+        "timestamp" => (
+            DataType::Timestamp,
+            DataType::TimestampArray,
+            "::std::time::SystemTime",
+        ),
+        _ => return None,
+    };
+
+    match array {
+        true => Some((array_type, format!("::std::vec::Vec<{}>", rust_type))),
+        false => Some((data_type, rust_type.to_string())),
+    }
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+// eg: VehicleIdentification -> vehicle_identification, ADAS -> adas, Row1 -> row1
+fn module_name(name: &str) -> String {
+    let chars: Vec<char> = name.chars().collect();
+    let mut snake = String::new();
+
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let previous = chars[i - 1];
+            let next_is_lower = chars.get(i + 1).is_some_and(|next| next.is_lowercase());
+            if previous.is_lowercase()
+                || previous.is_ascii_digit()
+                || (previous.is_uppercase() && next_is_lower)
+            {
+                snake.push('_');
+            }
+        }
+        snake.extend(c.to_lowercase());
+    }
+
+    identifier(&snake)
+}
+
+// a valid rust identifier, keywords are escaped, eg: type -> r#type
+fn identifier(name: &str) -> String {
+    const KEYWORDS: &[&str] = &[
+        "as", "async", "await", "box", "break", "const", "continue", "dyn", "else", "enum",
+        "extern", "false", "fn", "for", "if", "impl", "in", "let", "loop", "match", "mod", "move",
+        "mut", "pub", "ref", "return", "static", "struct", "trait", "true", "try", "type",
+        "unsafe", "use", "where", "while", "yield",
+    ];
+
+    let mut identifier: String = name
+        .chars()
+        .map(|c| match c.is_ascii_alphanumeric() || c == '_' {
+            true => c,
+            false => '_',
+        })
+        .collect();
+    if identifier.is_empty() || identifier.starts_with(|c: char| c.is_ascii_digit()) {
+        identifier.insert(0, '_');
+    }
+
+    match identifier.as_str() {
+        // can not be raw identifiers
+        "self" | "Self" | "super" | "crate" => format!("{}_", identifier),
+        keyword if KEYWORDS.contains(&keyword) => format!("r#{}", keyword),
+        _ => identifier,
+    }
+}
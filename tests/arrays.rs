@@ -0,0 +1,80 @@
+use databroker_proto::kuksa::val::v1::{
+    datapoint, parse_array, BoolArray, DataType, Int32Array, StringArray, Uint32Array,
+};
+use simple_kuksa_client::common::{str_to_value, ClientError, Value};
+
+#[test]
+fn parses_json_style_and_comma_lists() {
+    assert_eq!(
+        parse_array(r#"["a", "b,c", 'd\'e']"#).unwrap(),
+        vec!["a", "b,c", "d'e"]
+    );
+    assert_eq!(parse_array(" a , b ,c").unwrap(), vec!["a", "b", "c"]);
+    assert_eq!(parse_array("[]").unwrap(), Vec::<String>::new());
+    assert_eq!(parse_array("").unwrap(), Vec::<String>::new());
+
+    assert!(parse_array("[a, b").is_err());
+    assert!(parse_array(r#"["a"#).is_err());
+    assert!(parse_array("a, b,").is_err());
+    // empty elements have to be quoted
+    assert!(parse_array(", a").is_err());
+    assert!(parse_array("[a, , b]").is_err());
+    assert_eq!(parse_array(r#"["", a]"#).unwrap(), vec!["", "a"]);
+}
+
+#[test]
+fn str_to_value_parses_arrays() {
+    assert_eq!(
+        str_to_value(r#"["left", "right"]"#, DataType::StringArray).unwrap(),
+        Value::StringArray(StringArray {
+            values: vec!["left".to_string(), "right".to_string()]
+        })
+    );
+    assert_eq!(
+        str_to_value("true, false", DataType::BooleanArray).unwrap(),
+        Value::BoolArray(BoolArray {
+            values: vec![true, false]
+        })
+    );
+    assert_eq!(
+        str_to_value("[-128, 0, 127]", DataType::Int8Array).unwrap(),
+        Value::Int32Array(Int32Array {
+            values: vec![-128, 0, 127]
+        })
+    );
+    assert_eq!(
+        str_to_value("[0, 65535]", DataType::Uint16Array).unwrap(),
+        Value::Uint32Array(Uint32Array {
+            values: vec![0, 65535]
+        })
+    );
+}
+
+#[test]
+fn str_to_value_checks_array_ranges() {
+    for (input, datatype) in [
+        ("[1, 128]", DataType::Int8Array),
+        ("[-32769]", DataType::Int16Array),
+        ("[256]", DataType::Uint8Array),
+        ("[-1]", DataType::Uint16Array),
+        ("[1, x]", DataType::Int32Array),
+        (", true", DataType::BooleanArray),
+        ("a,, b", DataType::StringArray),
+    ] {
+        assert!(matches!(
+            str_to_value(input, datatype),
+            Err(ClientError::Parse(_))
+        ));
+    }
+}
+
+#[test]
+fn value_new_parses_arrays() {
+    assert_eq!(
+        datapoint::Value::new(DataType::Int16Array, "[-300, 300]").unwrap(),
+        Value::Int32Array(Int32Array {
+            values: vec![-300, 300]
+        })
+    );
+    assert!(datapoint::Value::new(DataType::Uint8Array, "[1, 300]").is_err());
+}
@@ -0,0 +1,98 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use databroker_proto::kuksa::val::v1::{DataType, EntryType};
+use simple_kuksa_client::common::ClientError;
+use simple_kuksa_client::testing::{MockDatabroker, MockServer};
+use simple_kuksa_client::{KuksaClient, TokenProvider};
+
+const SPEED: &str = "Vehicle.Speed";
+
+async fn connected_client(
+    databroker: &MockDatabroker,
+    token_provider: TokenProvider,
+) -> (MockServer, KuksaClient) {
+    let server = databroker
+        .clone()
+        .with_signal(SPEED, DataType::Float, EntryType::Sensor)
+        .serve()
+        .unwrap();
+
+    let client = KuksaClient::new(&server.url()).with_token(token_provider);
+    client.connect().await.unwrap();
+    (server, client)
+}
+
+#[tokio::test]
+async fn reuses_auth_uuid_issued_for_token() {
+    let databroker = MockDatabroker::new().with_token("writer", true);
+    let (_server, client) =
+        connected_client(&databroker, TokenProvider::Static("writer".to_string())).await;
+
+    assert_eq!(client.auth_uuid(), None);
+    client.set_current_value(SPEED, "10").await.unwrap();
+    assert!(client.auth_uuid().is_some());
+
+    client.get_current_value(SPEED).await.unwrap();
+    client.set_current_value(SPEED, "20").await.unwrap();
+    assert_eq!(databroker.token_calls(), 1);
+}
+
+#[tokio::test]
+async fn maps_rejected_credentials_to_auth_error() {
+    let databroker = MockDatabroker::new()
+        .with_token("writer", true)
+        .with_token("reader", false);
+    let (_server, client) =
+        connected_client(&databroker, TokenProvider::Static("reader".to_string())).await;
+
+    client.get_current_value(SPEED).await.unwrap();
+    match client.set_current_value(SPEED, "10").await {
+        Err(ClientError::PermissionDenied { path, .. }) => assert_eq!(path.as_deref(), Some(SPEED)),
+        other => panic!("unexpected result {:?}", other),
+    }
+
+    let databroker = MockDatabroker::new().with_token("writer", true);
+    let (_server, client) =
+        connected_client(&databroker, TokenProvider::Static("forged".to_string())).await;
+    match client.get_current_value(SPEED).await {
+        Err(ClientError::Auth(status)) => assert_eq!(status.code(), tonic::Code::Unauthenticated),
+        other => panic!("unexpected result {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn asks_callback_for_every_request() {
+    let calls = Arc::new(AtomicUsize::new(0));
+    let provider = {
+        let calls = calls.clone();
+        TokenProvider::callback(move || {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Ok("reader".to_string())
+        })
+    };
+    let databroker = MockDatabroker::new().with_token("reader", false);
+    let (_server, client) = connected_client(&databroker, provider).await;
+    let connect_calls = calls.load(Ordering::SeqCst);
+
+    client.get_current_value(SPEED).await.unwrap();
+    client.get_current_value(SPEED).await.unwrap();
+    assert_eq!(calls.load(Ordering::SeqCst) - connect_calls, 2);
+}
+
+#[tokio::test]
+async fn reads_token_from_file() {
+    let databroker = MockDatabroker::new().with_token("from-file", false);
+    let path = std::env::temp_dir().join(format!("kuksa-token-{}", std::process::id()));
+    std::fs::write(&path, "from-file\n").unwrap();
+
+    let (_server, client) = connected_client(&databroker, TokenProvider::File(path.clone())).await;
+    let result = client.get_current_value(SPEED).await;
+    std::fs::remove_file(&path).unwrap();
+    result.unwrap();
+
+    assert!(matches!(
+        client.get_current_value(SPEED).await,
+        Err(ClientError::Config(_))
+    ));
+}
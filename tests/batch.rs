@@ -0,0 +1,180 @@
+mod common;
+
+use databroker_proto::kuksa::val::v1::{DataEntry, Error, Field, GetResponse, View};
+use simple_kuksa_client::common::{value_from_datapoint, ClientError, Value};
+use simple_kuksa_client::kuksa_client::EntryRequest;
+use simple_kuksa_client::testing::Method;
+use simple_kuksa_client::{BatchGet, KuksaClient};
+
+use common::databroker;
+
+const SPEED: &str = "Vehicle.Speed";
+const ROW1_LEFT: &str = "Vehicle.Cabin.Door.Row1.Left.IsOpen";
+const ROW1_RIGHT: &str = "Vehicle.Cabin.Door.Row1.Right.IsOpen";
+
+fn current_value(path: &str) -> EntryRequest {
+    EntryRequest {
+        path: path.to_string(),
+        view: View::CurrentValue.into(),
+        fields: vec![Field::Value.into()],
+    }
+}
+
+#[tokio::test]
+async fn gets_many_paths_in_one_request() {
+    let databroker = databroker();
+    let server = databroker.serve().unwrap();
+    let client = KuksaClient::new(&server.url());
+    client.set_current_value(SPEED, "88").await.unwrap();
+    client.set_current_value(ROW1_LEFT, "true").await.unwrap();
+
+    let gets = databroker.calls(Method::Get);
+    let batch = client
+        .batch_get(vec![
+            current_value(SPEED),
+            current_value("Vehicle.Cabin.Door"),
+            current_value("Vehicle.Unknown"),
+        ])
+        .await
+        .unwrap();
+    assert_eq!(databroker.calls(Method::Get), gets + 1);
+
+    assert_eq!(batch.entries.len(), 3);
+    assert_eq!(
+        value_from_datapoint(batch.entries[SPEED].value.clone()),
+        Some(Value::Float(88.0))
+    );
+    assert_eq!(
+        value_from_datapoint(batch.entries[ROW1_LEFT].value.clone()),
+        Some(Value::Bool(true))
+    );
+    assert!(batch.entries[ROW1_RIGHT].value.is_none());
+
+    assert!(!batch.is_complete());
+    assert_eq!(batch.errors.len(), 1);
+    assert_eq!(batch.errors["Vehicle.Unknown"].code, 404);
+    assert!(batch.error.is_none());
+}
+
+#[test]
+fn keeps_the_response_level_error() {
+    let error = Error {
+        code: 500,
+        reason: "internal_error".to_string(),
+        message: "partially served".to_string(),
+    };
+    let response = GetResponse {
+        entries: vec![DataEntry {
+            path: SPEED.to_string(),
+            ..Default::default()
+        }],
+        errors: vec![],
+        error: Some(error.clone()),
+    };
+
+    let batch = BatchGet::try_from(response).unwrap();
+    assert_eq!(batch.entries.len(), 1);
+    assert_eq!(batch.error, Some(error.clone()));
+    assert!(!batch.is_complete());
+
+    // nothing to attribute the error to
+    let response = GetResponse {
+        entries: vec![],
+        errors: vec![],
+        error: Some(error),
+    };
+    assert!(matches!(
+        BatchGet::try_from(response),
+        Err(ClientError::Function(_))
+    ));
+}
+
+#[tokio::test]
+async fn sets_many_target_values_at_once() {
+    let server = databroker().serve().unwrap();
+    let client = KuksaClient::new(&server.url());
+
+    client
+        .batch_set_target_values(vec![
+            (ROW1_LEFT, Value::Bool(true)),
+            (ROW1_RIGHT, Value::Bool(true)),
+        ])
+        .await
+        .unwrap();
+
+    for path in [ROW1_LEFT, ROW1_RIGHT] {
+        let datapoint = client.get_target_value(path).await.unwrap();
+        assert_eq!(value_from_datapoint(datapoint), Some(Value::Bool(true)));
+    }
+}
+
+#[tokio::test]
+async fn rejects_whole_batch_with_errors_by_path() {
+    let server = databroker().serve().unwrap();
+    let client = KuksaClient::new(&server.url());
+
+    let result = client
+        .batch_set_target_values(vec![
+            (ROW1_LEFT, Value::Bool(true)),
+            (ROW1_RIGHT, Value::Float(1.0)),
+            (SPEED, Value::Float(1.0)),
+            ("Vehicle.Unknown", Value::Bool(true)),
+        ])
+        .await;
+
+    let errors = match result {
+        Err(ClientError::Entries(errors)) => errors,
+        other => panic!("unexpected result {:?}", other),
+    };
+    assert_eq!(errors.len(), 3);
+    assert!(matches!(
+        errors[ROW1_RIGHT],
+        ClientError::TypeMismatch { .. }
+    ));
+    assert!(matches!(errors[SPEED], ClientError::NotActuator { .. }));
+    assert!(matches!(
+        errors["Vehicle.Unknown"],
+        ClientError::NotFound { .. }
+    ));
+    assert_eq!(errors[SPEED].path(), Some(SPEED));
+
+    // nothing was sent
+    assert!(client.get_target_value(ROW1_LEFT).await.unwrap().is_none());
+
+    client
+        .batch_set_current_values(vec![
+            (SPEED, Value::Float(5.0)),
+            (ROW1_LEFT, Value::Bool(false)),
+        ])
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn parses_value_strings_with_the_datatype_of_each_signal() {
+    let databroker = databroker();
+    let server = databroker.serve().unwrap();
+    let client = KuksaClient::new(&server.url());
+
+    client
+        .set_current_values(vec![(SPEED, "88.5"), (ROW1_LEFT, "true")])
+        .await
+        .unwrap();
+    assert_eq!(databroker.value(SPEED), Some(Value::Float(88.5)));
+    assert_eq!(databroker.value(ROW1_LEFT), Some(Value::Bool(true)));
+    // the metadata of both is fetched with a single Get
+    assert_eq!(databroker.calls(Method::Get), 1);
+
+    match client
+        .set_target_values(vec![(ROW1_LEFT, "false"), (ROW1_RIGHT, "open")])
+        .await
+    {
+        Err(ClientError::Entries(errors)) => {
+            assert_eq!(errors.len(), 1);
+            assert!(matches!(errors[ROW1_RIGHT], ClientError::Parse(_)));
+        }
+        other => panic!("expected errors by path, got {:?}", other),
+    }
+    // nothing was sent
+    assert_eq!(databroker.target(ROW1_LEFT), None);
+}
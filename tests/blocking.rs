@@ -0,0 +1,121 @@
+mod common;
+
+use std::thread;
+use std::time::Duration;
+
+use databroker_proto::kuksa::val::v1::{Field, SubscribeEntry, View};
+use simple_kuksa_client::blocking::KuksaClient;
+use simple_kuksa_client::common::{ClientError, Value};
+use simple_kuksa_client::{SubscriptionEvent, SubscriptionOptions};
+use tonic::Status;
+
+use common::databroker;
+
+const SPEED: &str = "Vehicle.Speed";
+const POSITION: &str = "Vehicle.Cabin.Seat.Row1.Pos1.Position";
+
+fn value(response: &databroker_proto::kuksa::val::v1::SubscribeResponse) -> Option<Value> {
+    let entry = response.updates[0].entry.clone()?;
+    entry.value?.value
+}
+
+#[test]
+fn gets_and_sets_without_a_runtime() {
+    let databroker = databroker();
+    let server = databroker.serve().unwrap();
+    let client = KuksaClient::new(&server.url()).unwrap();
+
+    assert_eq!(client.server_info().unwrap().name, "databroker");
+    assert_eq!(client.get_metadata(POSITION).unwrap().len(), 1);
+
+    client.set_current_value(SPEED, "88.5").unwrap();
+    let datapoint = client.get_current_value(SPEED).unwrap().unwrap();
+    assert_eq!(datapoint.value, Some(Value::Float(88.5)));
+
+    client.set_target_value(POSITION, "300").unwrap();
+    assert_eq!(databroker.target(POSITION), Some(Value::Uint32(300)));
+    assert!(matches!(
+        client.set_target_value(SPEED, "1"),
+        Err(ClientError::NotActuator { .. })
+    ));
+
+    client
+        .batch_set_current_values(vec![
+            (SPEED, Value::Float(10.0)),
+            (POSITION, Value::Uint32(5)),
+        ])
+        .unwrap();
+    assert_eq!(databroker.value(POSITION), Some(Value::Uint32(5)));
+}
+
+#[test]
+fn iterates_over_subscriptions() {
+    let databroker = databroker().with_value(SPEED, Value::Float(0.0));
+    let server = databroker.serve().unwrap();
+    let client = KuksaClient::new(&server.url()).unwrap();
+
+    let mut updates = client.subscribe_current_value(SPEED).unwrap();
+    assert_eq!(
+        value(&updates.next().unwrap().unwrap()),
+        Some(Value::Float(0.0))
+    );
+    assert!(matches!(
+        updates.next_timeout(Duration::from_millis(50)),
+        Some(Err(ClientError::Timeout { .. }))
+    ));
+
+    // a clone in another thread feeds the values
+    let feeder = client.clone();
+    let handle = thread::spawn(move || {
+        for speed in 1..=3 {
+            feeder.set_current_value(SPEED, &speed.to_string()).unwrap();
+        }
+    });
+
+    let speeds: Vec<Option<Value>> = updates
+        .by_ref()
+        .take(3)
+        .map(|response| value(&response.unwrap()))
+        .collect();
+    assert_eq!(
+        speeds,
+        vec![
+            Some(Value::Float(1.0)),
+            Some(Value::Float(2.0)),
+            Some(Value::Float(3.0))
+        ]
+    );
+    handle.join().unwrap();
+
+    // the stream ends with the server
+    server.stop();
+    assert!(updates.all(|response| response.is_err()));
+}
+
+#[test]
+fn resubscribes_managed_subscriptions() {
+    let databroker = databroker();
+    let server = databroker.serve().unwrap();
+    let client = KuksaClient::new(&server.url()).unwrap();
+
+    let mut events = client.subscribe_managed(
+        vec![SubscribeEntry {
+            path: SPEED.to_string(),
+            view: View::CurrentValue.into(),
+            fields: vec![Field::Value.into()],
+        }],
+        SubscriptionOptions::new(),
+    );
+    assert!(matches!(events.next(), Some(SubscriptionEvent::Update(_))));
+
+    databroker.close_subscriptions(Status::unavailable("restarting"));
+    assert!(matches!(
+        events.next(),
+        Some(SubscriptionEvent::Interrupted(_))
+    ));
+    assert!(matches!(
+        events.next(),
+        Some(SubscriptionEvent::Resumed { .. })
+    ));
+    assert!(events.next_timeout(Duration::from_millis(50)).is_some());
+}
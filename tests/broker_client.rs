@@ -0,0 +1,135 @@
+mod common;
+
+use databroker_proto::sdv::databroker::v1::datapoint::{Failure, Value};
+use databroker_proto::sdv::databroker::v1::{DataType, EntryType};
+use simple_kuksa_client::common::ClientError;
+use simple_kuksa_client::BrokerClient;
+use tokio_stream::StreamExt;
+use tonic::Status;
+
+use common::broker::BrokerStandIn;
+
+const SPEED: &str = "Vehicle.Speed";
+const DOOR: &str = "Vehicle.Cabin.Door.Row1.Left.IsOpen";
+const VIN: &str = "Vehicle.VehicleIdentification.VIN";
+
+fn stand_in() -> BrokerStandIn {
+    BrokerStandIn::new()
+        .with_datapoint(SPEED, DataType::Float, EntryType::Sensor)
+        .with_datapoint(DOOR, DataType::Bool, EntryType::Actuator)
+        .with_datapoint(VIN, DataType::String, EntryType::Attribute)
+}
+
+#[tokio::test]
+async fn sets_and_gets_datapoints() {
+    let server = stand_in().serve(None);
+    let client = BrokerClient::new(&server.url());
+
+    client
+        .set_datapoints(vec![
+            (SPEED, Value::FloatValue(88.0)),
+            (DOOR, Value::BoolValue(true)),
+        ])
+        .await
+        .unwrap();
+
+    let datapoints = client.get_datapoints(vec![SPEED, DOOR, VIN]).await.unwrap();
+    assert_eq!(datapoints[SPEED].value, Some(Value::FloatValue(88.0)));
+    assert_eq!(datapoints[DOOR].value, Some(Value::BoolValue(true)));
+    // never set: NotAvailable
+    assert_eq!(datapoints[VIN].value, None);
+
+    let metadata = client.get_metadata(vec![SPEED]).await.unwrap();
+    assert_eq!(metadata.len(), 1);
+    assert_eq!(metadata[0].data_type, DataType::Float as i32);
+    assert_eq!(client.get_metadata(vec![]).await.unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn maps_failures_to_errors_by_path() {
+    let server = stand_in()
+        .with_failure(VIN, Failure::AccessDenied)
+        .serve(None);
+    let client = BrokerClient::new(&server.url());
+
+    match client
+        .get_datapoints(vec![SPEED, VIN, "Vehicle.Unknown"])
+        .await
+    {
+        Err(ClientError::Entries(errors)) => {
+            assert_eq!(errors.len(), 2);
+            assert!(matches!(errors[VIN], ClientError::PermissionDenied { .. }));
+            assert!(matches!(
+                errors["Vehicle.Unknown"],
+                ClientError::NotFound { .. }
+            ));
+        }
+        other => panic!("expected errors by path, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn maps_datapoint_errors_of_set() {
+    let server = stand_in().serve(None);
+    let client = BrokerClient::new(&server.url());
+
+    match client
+        .set_datapoints(vec![
+            (SPEED, Value::StringValue("fast".to_string())),
+            ("Vehicle.Unknown", Value::BoolValue(true)),
+            (DOOR, Value::BoolValue(false)),
+        ])
+        .await
+    {
+        Err(ClientError::Entries(errors)) => {
+            assert_eq!(errors.len(), 2);
+            assert!(matches!(errors[SPEED], ClientError::TypeMismatch { .. }));
+            assert!(matches!(
+                errors["Vehicle.Unknown"],
+                ClientError::NotFound { .. }
+            ));
+        }
+        other => panic!("expected errors by path, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn does_not_send_a_failed_set_again() {
+    let stand_in = stand_in();
+    let server = stand_in.serve(None);
+    let client = BrokerClient::new(&server.url());
+    client.connect().await.unwrap();
+
+    // the databroker may have applied it before the call failed
+    stand_in.fail_next("SetDatapoints", Status::unavailable("restarting"));
+    let result = client
+        .set_datapoints(vec![(SPEED, Value::FloatValue(60.0))])
+        .await;
+    assert!(matches!(result, Err(ClientError::Unavailable(_))));
+    assert_eq!(stand_in.calls("SetDatapoints"), 1);
+
+    client
+        .set_datapoints(vec![(SPEED, Value::FloatValue(60.0))])
+        .await
+        .unwrap();
+    assert_eq!(stand_in.calls("SetDatapoints"), 2);
+}
+
+#[tokio::test]
+async fn subscribes_with_a_query() {
+    let server = stand_in().serve(None);
+    let client = BrokerClient::new(&server.url());
+    let feeder = client.clone();
+
+    let mut stream = client
+        .subscribe(&format!("SELECT {}", SPEED))
+        .await
+        .unwrap();
+    feeder
+        .set_datapoints(vec![(SPEED, Value::FloatValue(42.0))])
+        .await
+        .unwrap();
+
+    let reply = stream.next().await.unwrap().unwrap();
+    assert_eq!(reply.fields[SPEED].value, Some(Value::FloatValue(42.0)));
+}
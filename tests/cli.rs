@@ -0,0 +1,90 @@
+mod common;
+
+use std::process::{Command, Output};
+
+use common::databroker;
+
+const SPEED: &str = "Vehicle.Speed";
+const DOOR: &str = "Vehicle.Cabin.Door.Row1.Left.IsOpen";
+
+fn kuksa_cli(address: &str, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_kuksa-cli"))
+        .env("KUKSA_ADDRESS", address)
+        .env_remove("KUKSA_TOKEN")
+        .env_remove("KUKSA_TOKEN_FILE")
+        .env_remove("KUKSA_CA_CERT")
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+#[test]
+fn sets_and_gets_many_paths() {
+    let server = databroker().serve().unwrap();
+    let address = server.url();
+
+    let set = kuksa_cli(
+        &address,
+        &["set", "Vehicle.Speed=88.5", &format!("{}=true", DOOR)],
+    );
+    assert!(set.status.success(), "{:?}", set);
+
+    let set_target = kuksa_cli(&address, &["set-target", &format!("{}=false", DOOR)]);
+    assert!(set_target.status.success(), "{:?}", set_target);
+
+    let get = kuksa_cli(&address, &["get", SPEED, DOOR]);
+    assert!(get.status.success(), "{:?}", get);
+    let lines: Vec<String> = stdout(&get).lines().map(str::to_string).collect();
+    assert_eq!(lines.len(), 2);
+    assert!(lines[0].starts_with(&format!("{}: true (", DOOR)));
+    assert!(lines[1].starts_with(&format!("{}: 88.5 (", SPEED)));
+
+    let get_target = kuksa_cli(&address, &["get-target", DOOR]);
+    assert!(stdout(&get_target).starts_with(&format!("{}: false", DOOR)));
+}
+
+#[test]
+fn prints_json() {
+    let server = databroker().serve().unwrap();
+    let address = server.url();
+
+    kuksa_cli(&address, &["set", "Vehicle.Speed=42"]);
+    let get = kuksa_cli(&address, &["--json", "get", SPEED, "Vehicle.Unknown"]);
+
+    // the unknown path fails the command, but is part of the output
+    assert!(!get.status.success());
+    let json = stdout(&get);
+    assert!(
+        json.starts_with(r#"{"Vehicle.Speed":{"timestamp":""#),
+        "{}",
+        json
+    );
+    assert!(json.contains(r#""value":42.0}"#), "{}", json);
+    assert!(
+        json.contains(r#""Vehicle.Unknown":{"error":{"code":404,"#),
+        "{}",
+        json
+    );
+
+    let info = kuksa_cli(&address, &["server-info", "--json"]);
+    assert_eq!(
+        stdout(&info).trim(),
+        r#"{"name":"databroker","version":"0.4.4"}"#
+    );
+}
+
+#[test]
+fn rejects_invalid_values() {
+    let server = databroker().serve().unwrap();
+
+    let set = kuksa_cli(&server.url(), &["set", "Vehicle.Speed=fast"]);
+    assert!(!set.status.success());
+    assert!(String::from_utf8_lossy(&set.stderr).contains("Parse Float error"));
+
+    let malformed = kuksa_cli(&server.url(), &["set", "Vehicle.Speed"]);
+    assert!(!malformed.status.success());
+}
@@ -0,0 +1,347 @@
+// in-process stand-in for the sdv.databroker.v1 Broker and Collector services
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::{Stream, StreamExt};
+use tonic::transport::ServerTlsConfig;
+use tonic::{Request, Response, Status, Streaming};
+
+use databroker_proto::sdv::databroker::v1::broker_server::{Broker, BrokerServer};
+use databroker_proto::sdv::databroker::v1::collector_server::{Collector, CollectorServer};
+use databroker_proto::sdv::databroker::v1::datapoint::{Failure, Value};
+use databroker_proto::sdv::databroker::v1::{
+    ChangeType, DataType, Datapoint, DatapointError, EntryType, GetDatapointsReply,
+    GetDatapointsRequest, GetMetadataReply, GetMetadataRequest, Metadata, RegisterDatapointsReply,
+    RegisterDatapointsRequest, SetDatapointsReply, SetDatapointsRequest, StreamDatapointsReply,
+    StreamDatapointsRequest, SubscribeReply, SubscribeRequest, UpdateDatapointsReply,
+    UpdateDatapointsRequest,
+};
+
+use simple_kuksa_client::testing::MockServer;
+
+#[derive(Clone)]
+pub struct BrokerStandIn {
+    metadata: Arc<Mutex<Vec<Metadata>>>,
+    values: Arc<Mutex<HashMap<String, Datapoint>>>,
+    // paths changed by a set, for the subscribers
+    changes: broadcast::Sender<String>,
+    // every query received by Subscribe
+    pub queries: Arc<Mutex<Vec<String>>>,
+    // calls received by method name, eg: "SetDatapoints"
+    calls: Arc<Mutex<HashMap<&'static str, usize>>>,
+    // the next call of the method fails with the status
+    failures: Arc<Mutex<HashMap<&'static str, Status>>>,
+}
+
+impl BrokerStandIn {
+    pub fn new() -> Self {
+        BrokerStandIn {
+            metadata: Default::default(),
+            values: Default::default(),
+            changes: broadcast::channel(64).0,
+            queries: Default::default(),
+            calls: Default::default(),
+            failures: Default::default(),
+        }
+    }
+
+    pub fn calls(&self, method: &str) -> usize {
+        let calls = self.calls.lock().unwrap();
+        calls.get(method).copied().unwrap_or(0)
+    }
+
+    pub fn fail_next(&self, method: &'static str, status: Status) {
+        self.failures.lock().unwrap().insert(method, status);
+    }
+
+    // count the call, fail it if a failure is pending
+    #[allow(clippy::result_large_err)]
+    fn call(&self, method: &'static str) -> Result<(), Status> {
+        *self.calls.lock().unwrap().entry(method).or_default() += 1;
+        match self.failures.lock().unwrap().remove(method) {
+            Some(status) => Err(status),
+            None => Ok(()),
+        }
+    }
+
+    pub fn with_datapoint(self, name: &str, data_type: DataType, entry_type: EntryType) -> Self {
+        self.register(name, data_type, entry_type, ChangeType::OnChange);
+        self
+    }
+
+    // id of the datapoint, the existing one if the name is already known
+    fn register(
+        &self,
+        name: &str,
+        data_type: DataType,
+        entry_type: EntryType,
+        change_type: ChangeType,
+    ) -> i32 {
+        let mut metadata = self.metadata.lock().unwrap();
+        if let Some(existing) = metadata.iter().find(|metadata| metadata.name == name) {
+            return existing.id;
+        }
+
+        let id = metadata.len() as i32;
+        metadata.push(Metadata {
+            id,
+            entry_type: entry_type.into(),
+            name: name.to_string(),
+            data_type: data_type.into(),
+            change_type: change_type.into(),
+            description: String::new(),
+        });
+        id
+    }
+
+    // report the failure instead of a value
+    pub fn with_failure(self, name: &str, failure: Failure) -> Self {
+        self.values.lock().unwrap().insert(
+            name.to_string(),
+            Datapoint {
+                timestamp: None,
+                value: Some(Value::FailureValue(failure.into())),
+            },
+        );
+        self
+    }
+
+    fn metadata(&self, name: &str) -> Option<Metadata> {
+        let metadata = self.metadata.lock().unwrap();
+        metadata
+            .iter()
+            .find(|metadata| metadata.name == name)
+            .cloned()
+    }
+
+    fn value(&self, name: &str) -> Datapoint {
+        let values = self.values.lock().unwrap();
+        values.get(name).cloned().unwrap_or(Datapoint {
+            timestamp: None,
+            value: Some(Value::FailureValue(Failure::NotAvailable.into())),
+        })
+    }
+
+    pub fn serve(&self, tls: Option<ServerTlsConfig>) -> MockServer {
+        self.serve_on("127.0.0.1:0".parse().unwrap(), tls)
+    }
+
+    pub fn serve_on(&self, address: SocketAddr, tls: Option<ServerTlsConfig>) -> MockServer {
+        let broker = BrokerServer::new(self.clone());
+        let collector = CollectorServer::new(self.clone());
+        MockServer::start(address, tls, move |builder| {
+            builder.add_service(broker).add_service(collector)
+        })
+        .unwrap()
+    }
+
+    // store the value if the datapoint exists and the datatype matches
+    fn store(&self, name: String, datapoint: Datapoint) -> Result<(), DatapointError> {
+        let metadata = self
+            .metadata(&name)
+            .ok_or(DatapointError::UnknownDatapoint)?;
+        let data_type = DataType::try_from(metadata.data_type).unwrap();
+
+        match datapoint.value {
+            Some(ref value) if matches_data_type(value, data_type) => {
+                self.values.lock().unwrap().insert(name.clone(), datapoint);
+                let _ = self.changes.send(name);
+                Ok(())
+            }
+            _ => Err(DatapointError::InvalidType),
+        }
+    }
+
+    fn name(&self, id: i32) -> Option<String> {
+        let metadata = self.metadata.lock().unwrap();
+        metadata
+            .iter()
+            .find(|metadata| metadata.id == id)
+            .map(|metadata| metadata.name.clone())
+    }
+
+    fn update(&self, datapoints: HashMap<i32, Datapoint>) -> HashMap<i32, i32> {
+        let mut errors = HashMap::new();
+
+        for (id, datapoint) in datapoints {
+            let result = match self.name(id) {
+                Some(name) => self.store(name, datapoint),
+                None => Err(DatapointError::UnknownDatapoint),
+            };
+            if let Err(error) = result {
+                errors.insert(id, error.into());
+            }
+        }
+
+        errors
+    }
+}
+
+fn matches_data_type(value: &Value, data_type: DataType) -> bool {
+    matches!(
+        (data_type, value),
+        (DataType::String, Value::StringValue(_))
+            | (DataType::Bool, Value::BoolValue(_))
+            | (DataType::Int32, Value::Int32Value(_))
+            | (DataType::Int64, Value::Int64Value(_))
+            | (DataType::Uint32, Value::Uint32Value(_))
+            | (DataType::Uint64, Value::Uint64Value(_))
+            | (DataType::Float, Value::FloatValue(_))
+            | (DataType::Double, Value::DoubleValue(_))
+    )
+}
+
+// "SELECT a, b WHERE ..." --> [a, b]
+#[allow(clippy::result_large_err)]
+fn selected_fields(query: &str) -> Result<Vec<String>, Status> {
+    let select = query
+        .trim()
+        .strip_prefix("SELECT")
+        .ok_or_else(|| Status::invalid_argument("Query must start with SELECT"))?;
+    let fields = select.split(" WHERE ").next().unwrap_or_default();
+
+    Ok(fields
+        .split(',')
+        .map(|field| field.trim().to_string())
+        .filter(|field| !field.is_empty())
+        .collect())
+}
+
+#[tonic::async_trait]
+impl Broker for BrokerStandIn {
+    async fn get_datapoints(
+        &self,
+        request: Request<GetDatapointsRequest>,
+    ) -> Result<Response<GetDatapointsReply>, Status> {
+        let mut datapoints = HashMap::new();
+
+        for name in request.into_inner().datapoints {
+            let datapoint = match self.metadata(&name) {
+                Some(_) => self.value(&name),
+                None => Datapoint {
+                    timestamp: None,
+                    value: Some(Value::FailureValue(Failure::UnknownDatapoint.into())),
+                },
+            };
+            datapoints.insert(name, datapoint);
+        }
+
+        Ok(Response::new(GetDatapointsReply { datapoints }))
+    }
+
+    async fn set_datapoints(
+        &self,
+        request: Request<SetDatapointsRequest>,
+    ) -> Result<Response<SetDatapointsReply>, Status> {
+        self.call("SetDatapoints")?;
+        let mut errors = HashMap::new();
+
+        for (name, datapoint) in request.into_inner().datapoints {
+            if let Err(error) = self.store(name.clone(), datapoint) {
+                errors.insert(name, error.into());
+            }
+        }
+
+        Ok(Response::new(SetDatapointsReply { errors }))
+    }
+
+    type SubscribeStream = Pin<Box<dyn Stream<Item = Result<SubscribeReply, Status>> + Send>>;
+
+    async fn subscribe(
+        &self,
+        request: Request<SubscribeRequest>,
+    ) -> Result<Response<Self::SubscribeStream>, Status> {
+        let query = request.into_inner().query;
+        self.queries.lock().unwrap().push(query.clone());
+        let fields = selected_fields(&query)?;
+        if let Some(unknown) = fields.iter().find(|field| self.metadata(field).is_none()) {
+            return Err(Status::invalid_argument(format!(
+                "Unknown field {}",
+                unknown
+            )));
+        }
+
+        let stand_in = self.clone();
+        let stream = BroadcastStream::new(self.changes.subscribe()).filter_map(move |change| {
+            let changed = change.ok()?;
+            if !fields.contains(&changed) {
+                return None;
+            }
+            let values = fields
+                .iter()
+                .map(|field| (field.clone(), stand_in.value(field)))
+                .collect();
+            Some(Ok(SubscribeReply { fields: values }))
+        });
+
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    async fn get_metadata(
+        &self,
+        request: Request<GetMetadataRequest>,
+    ) -> Result<Response<GetMetadataReply>, Status> {
+        let names = request.into_inner().names;
+        let metadata = self.metadata.lock().unwrap();
+        let list = metadata
+            .iter()
+            .filter(|metadata| names.is_empty() || names.contains(&metadata.name))
+            .cloned()
+            .collect();
+
+        Ok(Response::new(GetMetadataReply { list }))
+    }
+}
+
+#[tonic::async_trait]
+impl Collector for BrokerStandIn {
+    async fn register_datapoints(
+        &self,
+        request: Request<RegisterDatapointsRequest>,
+    ) -> Result<Response<RegisterDatapointsReply>, Status> {
+        let mut results = HashMap::new();
+
+        for metadata in request.into_inner().list {
+            let data_type = DataType::try_from(metadata.data_type)
+                .map_err(|_| Status::invalid_argument("Unknown data type"))?;
+            let change_type = ChangeType::try_from(metadata.change_type)
+                .map_err(|_| Status::invalid_argument("Unknown change type"))?;
+            let id = self.register(&metadata.name, data_type, EntryType::Sensor, change_type);
+            results.insert(metadata.name, id);
+        }
+
+        Ok(Response::new(RegisterDatapointsReply { results }))
+    }
+
+    async fn update_datapoints(
+        &self,
+        request: Request<UpdateDatapointsRequest>,
+    ) -> Result<Response<UpdateDatapointsReply>, Status> {
+        self.call("UpdateDatapoints")?;
+        let errors = self.update(request.into_inner().datapoints);
+
+        Ok(Response::new(UpdateDatapointsReply { errors }))
+    }
+
+    type StreamDatapointsStream =
+        Pin<Box<dyn Stream<Item = Result<StreamDatapointsReply, Status>> + Send>>;
+
+    // answers every request, with the errors if there are any
+    #[allow(clippy::result_large_err)]
+    async fn stream_datapoints(
+        &self,
+        request: Request<Streaming<StreamDatapointsRequest>>,
+    ) -> Result<Response<Self::StreamDatapointsStream>, Status> {
+        let stand_in = self.clone();
+        let stream = request.into_inner().map(move |request| {
+            let errors = stand_in.update(request?.datapoints);
+            Ok(StreamDatapointsReply { errors })
+        });
+
+        Ok(Response::new(Box::pin(stream)))
+    }
+}
@@ -0,0 +1,14 @@
+// fixtures shared by the integration tests: the VAL tests start from databroker(), the
+// sdv.databroker.v1 tests use the stand-in of the broker module
+#![allow(dead_code)]
+
+pub mod broker;
+
+use simple_kuksa_client::testing::MockDatabroker;
+
+// the signals of tests/data/vss.json, without values
+pub fn databroker() -> MockDatabroker {
+    MockDatabroker::new()
+        .with_vss_json(include_str!("../data/vss.json"))
+        .unwrap()
+}
@@ -0,0 +1,156 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use databroker_proto::kuksa::val::v1::{DataType, EntryType};
+use simple_kuksa_client::common::Value;
+use simple_kuksa_client::testing::{Method, MockDatabroker};
+use simple_kuksa_client::{ConnectionState, KuksaClient};
+use tokio::task::JoinSet;
+use tokio_stream::StreamExt;
+use tonic::Status;
+
+const TASKS: usize = 16;
+const LATENCY: Duration = Duration::from_millis(200);
+
+fn assert_shareable<T: Clone + Send + Sync + 'static>() {}
+
+fn path(index: usize) -> String {
+    format!("Vehicle.Sensor{}", index)
+}
+
+fn databroker() -> MockDatabroker {
+    (0..TASKS).fold(common::databroker(), |databroker, index| {
+        databroker
+            .with_signal(&path(index), DataType::Uint32, EntryType::Sensor)
+            .with_value(&path(index), Value::Uint32(index as u32))
+    })
+}
+
+#[test]
+fn client_is_shareable() {
+    assert_shareable::<KuksaClient>();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn calls_of_many_tasks_run_in_parallel() {
+    let databroker = databroker();
+    let server = databroker.serve().unwrap();
+    let client = server.client();
+    // fill the metadata cache before the calls are slowed down
+    client.prefetch_metadata("Vehicle").await.unwrap();
+    databroker.set_latency(LATENCY);
+
+    let start = Instant::now();
+    let mut tasks = JoinSet::new();
+    for index in 0..TASKS {
+        let client = client.clone();
+        tasks.spawn(async move {
+            let datapoint = client.get_current_value(&path(index)).await.unwrap();
+            assert_eq!(datapoint.unwrap().value, Some(Value::Uint32(index as u32)));
+
+            let value = (index * 10).to_string();
+            client
+                .set_current_value(&path(index), &value)
+                .await
+                .unwrap();
+        });
+    }
+    while let Some(task) = tasks.join_next().await {
+        task.unwrap();
+    }
+
+    // serialized, the get and set of every task would take TASKS * 2 * LATENCY
+    assert!(start.elapsed() < LATENCY * 6, "took {:?}", start.elapsed());
+    for index in 0..TASKS {
+        assert_eq!(
+            databroker.value(&path(index)),
+            Some(Value::Uint32(index as u32 * 10))
+        );
+    }
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn clones_share_the_connection() {
+    let databroker = databroker();
+    let server = databroker.serve().unwrap();
+    let client = server.client();
+    let clone = client.clone();
+
+    // the first calls of all tasks wait for the same connection
+    let mut tasks = JoinSet::new();
+    for index in 0..TASKS {
+        let client = client.clone();
+        tasks.spawn(async move { client.get_metadata(&path(index)).await.unwrap() });
+    }
+    while let Some(task) = tasks.join_next().await {
+        assert_eq!(task.unwrap().len(), 1);
+    }
+    assert_eq!(clone.connection_state(), ConnectionState::Connected);
+
+    // and the metadata cache: no round-trip once a clone fetched it
+    databroker.set_latency(LATENCY);
+    let start = Instant::now();
+    clone.get_metadata(&path(0)).await.unwrap();
+    assert!(start.elapsed() < LATENCY);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn subscriptions_run_next_to_calls() {
+    let databroker = databroker();
+    let server = databroker.serve().unwrap();
+    let client = server.client();
+
+    let mut updates = client.subscribe_current_value(&path(0)).await.unwrap();
+    updates.next().await.unwrap().unwrap();
+
+    let setter = {
+        let client = client.clone();
+        tokio::spawn(async move {
+            for value in 1..=5 {
+                client
+                    .set_current_value(&path(0), &value.to_string())
+                    .await
+                    .unwrap();
+            }
+        })
+    };
+    // reads in between are not blocked by the open subscription
+    client.get_current_value(&path(1)).await.unwrap();
+
+    let mut last = None;
+    while last != Some(Value::Uint32(5)) {
+        let response = updates.next().await.unwrap().unwrap();
+        last = response.updates[0]
+            .entry
+            .clone()
+            .and_then(|entry| entry.value)
+            .and_then(|datapoint| datapoint.value);
+    }
+    setter.await.unwrap();
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn calls_failing_together_reconnect_once() {
+    let databroker = databroker();
+    let server = databroker.serve().unwrap();
+    let client = server.client();
+    client.prefetch_metadata("Vehicle").await.unwrap();
+    let connections = databroker.calls(Method::GetServerInfo);
+
+    // every call in flight fails on the same channel
+    databroker.set_latency(LATENCY);
+    databroker.fail_times(Method::Get, Status::unavailable("restarting"), TASKS);
+
+    let mut tasks = JoinSet::new();
+    for index in 0..TASKS {
+        let client = client.clone();
+        tasks.spawn(async move { client.get_current_value(&path(index)).await.unwrap() });
+    }
+    while let Some(task) = tasks.join_next().await {
+        assert!(task.unwrap().is_some());
+    }
+
+    // the version check runs once per new channel
+    assert_eq!(databroker.calls(Method::GetServerInfo), connections + 1);
+}
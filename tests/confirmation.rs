@@ -0,0 +1,154 @@
+mod common;
+
+use std::time::Duration;
+
+use simple_kuksa_client::common::{ClientError, Value};
+use simple_kuksa_client::testing::{Method, MockDatabroker};
+use simple_kuksa_client::ConfirmationOptions;
+use tonic::Status;
+
+const POSITION: &str = "Vehicle.Cabin.Seat.Row1.Pos1.Position";
+const SPEED: &str = "Vehicle.Speed";
+
+fn databroker() -> MockDatabroker {
+    common::databroker().with_value(POSITION, Value::Uint32(0))
+}
+
+// moves the seat through the steps once a target is set
+fn actuator(databroker: &MockDatabroker, steps: Vec<u32>) -> tokio::task::JoinHandle<()> {
+    let databroker = databroker.clone();
+    tokio::spawn(async move {
+        while databroker.target(POSITION).is_none() {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        for step in steps {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            databroker.set_value(POSITION, Value::Uint32(step));
+        }
+    })
+}
+
+#[tokio::test]
+async fn resolves_when_the_actuator_reaches_the_target() {
+    let databroker = databroker();
+    let server = databroker.serve().unwrap();
+    let client = server.client();
+
+    let moving = actuator(&databroker, vec![100, 200, 299]);
+    let reached = client
+        .set_target_value_confirmed(POSITION, "300", ConfirmationOptions::new().tolerance(1.0))
+        .await
+        .unwrap();
+    assert_eq!(reached, Value::Uint32(299));
+    assert_eq!(databroker.target(POSITION), Some(Value::Uint32(300)));
+    // the metadata is fetched once
+    assert_eq!(databroker.calls(Method::Get), 1);
+    moving.await.unwrap();
+
+    // already there
+    let reached = client
+        .set_target_value_confirmed(POSITION, "299", ConfirmationOptions::new())
+        .await
+        .unwrap();
+    assert_eq!(reached, Value::Uint32(299));
+}
+
+#[tokio::test]
+async fn reports_the_last_value_on_timeout() {
+    let databroker = databroker();
+    let server = databroker.serve().unwrap();
+    let client = server.client();
+
+    // the seat gets stuck
+    let moving = actuator(&databroker, vec![100, 250]);
+    let options = ConfirmationOptions::new()
+        .timeout(Duration::from_millis(300))
+        .tolerance(1.0);
+    match client
+        .set_target_value_confirmed(POSITION, "300", options)
+        .await
+    {
+        Err(ClientError::Timeout {
+            path, last_value, ..
+        }) => {
+            assert_eq!(path.as_deref(), Some(POSITION));
+            assert_eq!(last_value, Some(Value::Uint32(250)));
+        }
+        other => panic!("expected a timeout, got {:?}", other),
+    }
+    moving.await.unwrap();
+}
+
+#[tokio::test]
+async fn reports_the_path_when_the_stream_closes() {
+    let databroker = databroker();
+    let server = databroker.serve().unwrap();
+    let client = server.client();
+
+    let moving = actuator(&databroker, vec![100]);
+    let closing = {
+        let databroker = databroker.clone();
+        tokio::spawn(async move {
+            moving.await.unwrap();
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            databroker.close_subscriptions(Status::unavailable("restarting"));
+        })
+    };
+
+    match client
+        .set_target_value_confirmed(POSITION, "300", ConfirmationOptions::new())
+        .await
+    {
+        Err(ClientError::Timeout {
+            path, last_value, ..
+        }) => {
+            assert_eq!(path.as_deref(), Some(POSITION));
+            assert_eq!(last_value, Some(Value::Uint32(100)));
+        }
+        other => panic!("expected the wait to end, got {:?}", other),
+    }
+    closing.await.unwrap();
+}
+
+#[tokio::test]
+async fn checks_the_target_before_waiting() {
+    let databroker = databroker();
+    let server = databroker.serve().unwrap();
+    let client = server.client();
+
+    assert!(matches!(
+        client
+            .set_target_value_confirmed(SPEED, "10", ConfirmationOptions::new())
+            .await,
+        Err(ClientError::NotActuator { .. })
+    ));
+    assert!(matches!(
+        client
+            .set_target_value_confirmed(POSITION, "far", ConfirmationOptions::new())
+            .await,
+        Err(ClientError::Parse(_))
+    ));
+    assert!(matches!(
+        client
+            .set_target_value_confirmed("Vehicle.Unknown", "10", ConfirmationOptions::new())
+            .await,
+        Err(ClientError::NotFound { .. })
+    ));
+    assert_eq!(databroker.target(POSITION), None);
+}
+
+#[tokio::test]
+async fn confirms_typed_signals() {
+    let databroker = databroker();
+    let server = databroker.serve().unwrap();
+    let client = server.client();
+    let seat = client.signal::<u16>(POSITION).await.unwrap();
+
+    let moving = actuator(&databroker, vec![40, 80]);
+    let reached = seat
+        .set_target_confirmed(80, ConfirmationOptions::new())
+        .await
+        .unwrap();
+    assert_eq!(reached, 80);
+    moving.await.unwrap();
+}
@@ -0,0 +1,122 @@
+// generated from a VSS JSON spec by simple-kuksa-client, do not edit
+
+/// High-level vehicle data.
+#[allow(dead_code, non_upper_case_globals)]
+pub mod vehicle {
+    /// All in-cabin components, including doors.
+    pub mod cabin {
+        /// All doors, including windows and switches.
+        pub mod door {
+            /// All doors, including windows and switches.
+            pub mod row1 {
+                /// All doors, including windows and switches.
+                pub mod left {
+                    /// Is door open or closed
+                    pub const IsOpen: ::simple_kuksa_client::vss::VssSignal<bool> =
+                        ::simple_kuksa_client::vss::VssSignal::new(
+                            "Vehicle.Cabin.Door.Row1.Left.IsOpen",
+                            ::simple_kuksa_client::vss::DataType::Boolean,
+                            ::simple_kuksa_client::vss::EntryType::Actuator,
+                            None,
+                            "Is door open or closed",
+                            &[],
+                        );
+                }
+                /// All doors, including windows and switches.
+                pub mod right {
+                    /// Is door open or closed
+                    pub const IsOpen: ::simple_kuksa_client::vss::VssSignal<bool> =
+                        ::simple_kuksa_client::vss::VssSignal::new(
+                            "Vehicle.Cabin.Door.Row1.Right.IsOpen",
+                            ::simple_kuksa_client::vss::DataType::Boolean,
+                            ::simple_kuksa_client::vss::EntryType::Actuator,
+                            None,
+                            "Is door open or closed",
+                            &[],
+                        );
+                }
+            }
+        }
+        /// All seats.
+        pub mod seat {
+            /// All seats.
+            pub mod row1 {
+                /// All seats.
+                pub mod pos1 {
+                    /// Seat cooling / heating. 0 = off. -100 = max cold. +100 = max heat.
+                    pub const Heating: ::simple_kuksa_client::vss::VssSignal<i8> =
+                        ::simple_kuksa_client::vss::VssSignal::new(
+                            "Vehicle.Cabin.Seat.Row1.Pos1.Heating",
+                            ::simple_kuksa_client::vss::DataType::Int8,
+                            ::simple_kuksa_client::vss::EntryType::Actuator,
+                            Some("percent"),
+                            "Seat cooling / heating. 0 = off. -100 = max cold. +100 = max heat.",
+                            &["-100", "0", "100"],
+                        );
+                    /// Seat position on vehicle x-axis. Position is relative to the frontmost position supported by the seat. 0 = Frontmost position supported.
+                    pub const Position: ::simple_kuksa_client::vss::VssSignal<u16> =
+                        ::simple_kuksa_client::vss::VssSignal::new(
+                            "Vehicle.Cabin.Seat.Row1.Pos1.Position",
+                            ::simple_kuksa_client::vss::DataType::Uint16,
+                            ::simple_kuksa_client::vss::EntryType::Actuator,
+                            Some("mm"),
+                            "Seat position on vehicle x-axis. Position is relative to the frontmost position supported by the seat. 0 = Frontmost position supported.",
+                            &[],
+                        );
+                }
+            }
+        }
+    }
+    /// OBD data.
+    pub mod obd {
+        /// List of currently active DTCs formatted according OBD II (SAE-J2012DA_201812) standard ([P|C|B|U]XXXXX )
+        pub const DTCList: ::simple_kuksa_client::vss::VssSignal<::std::vec::Vec<::std::string::String>> =
+            ::simple_kuksa_client::vss::VssSignal::new(
+                "Vehicle.OBD.DTCList",
+                ::simple_kuksa_client::vss::DataType::StringArray,
+                ::simple_kuksa_client::vss::EntryType::Sensor,
+                None,
+                "List of currently active DTCs formatted according OBD II (SAE-J2012DA_201812) standard ([P|C|B|U]XXXXX )",
+                &[],
+            );
+    }
+    /// Powertrain data for battery management, etc.
+    pub mod powertrain {
+        /// Transmission-specific data, stopping at the drive shafts.
+        pub mod transmission {
+            /// Current gearbox performance mode.
+            pub const PerformanceMode: ::simple_kuksa_client::vss::VssSignal<::std::string::String> =
+                ::simple_kuksa_client::vss::VssSignal::new(
+                    "Vehicle.Powertrain.Transmission.PerformanceMode",
+                    ::simple_kuksa_client::vss::DataType::String,
+                    ::simple_kuksa_client::vss::EntryType::Actuator,
+                    None,
+                    "Current gearbox performance mode.",
+                    &["NORMAL", "SPORT", "ECONOMY", "SNOW", "RAIN"],
+                );
+        }
+    }
+    /// Vehicle speed.
+    pub const Speed: ::simple_kuksa_client::vss::VssSignal<f32> =
+        ::simple_kuksa_client::vss::VssSignal::new(
+            "Vehicle.Speed",
+            ::simple_kuksa_client::vss::DataType::Float,
+            ::simple_kuksa_client::vss::EntryType::Sensor,
+            Some("km/h"),
+            "Vehicle speed.",
+            &[],
+        );
+    /// Attributes that identify a vehicle.
+    pub mod vehicle_identification {
+        /// 17-character Vehicle Identification Number (VIN) as defined by ISO 3779.
+        pub const VIN: ::simple_kuksa_client::vss::VssSignal<::std::string::String> =
+            ::simple_kuksa_client::vss::VssSignal::new(
+                "Vehicle.VehicleIdentification.VIN",
+                ::simple_kuksa_client::vss::DataType::String,
+                ::simple_kuksa_client::vss::EntryType::Attribute,
+                None,
+                "17-character Vehicle Identification Number (VIN) as defined by ISO 3779.",
+                &[],
+            );
+    }
+}
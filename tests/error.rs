@@ -0,0 +1,180 @@
+mod common;
+
+use std::error::Error as _;
+
+use databroker_proto::kuksa::val::v1::{DataType, EntryType, Error};
+use simple_kuksa_client::common::{ClientError, Value};
+use simple_kuksa_client::testing::{Method, MockDatabroker};
+use tonic::{Code, Status};
+
+const SPEED: &str = "Vehicle.Speed";
+const HEATING: &str = "Vehicle.Cabin.Seat.Row1.Pos1.Heating";
+
+fn entry_error(code: u32, reason: &str, message: &str) -> Error {
+    Error {
+        code,
+        reason: reason.to_string(),
+        message: message.to_string(),
+    }
+}
+
+#[test]
+fn classifies_the_errors_of_entries() {
+    // as sent by the databroker
+    let error = ClientError::from_entry(
+        SPEED,
+        entry_error(
+            404,
+            "not found",
+            "no datapoint registered for path Vehicle.Speed",
+        ),
+    );
+    assert!(matches!(error, ClientError::NotFound { .. }));
+    assert_eq!(error.path(), Some(SPEED));
+    assert_eq!(error.to_string(), "Vehicle.Speed: no such signal");
+
+    let error = ClientError::from_entry(
+        SPEED,
+        entry_error(
+            400,
+            "value out of bounds",
+            "given value exceeds type's boundaries",
+        ),
+    );
+    assert!(matches!(error, ClientError::OutOfRange { .. }));
+    assert_eq!(
+        error.to_string(),
+        "Vehicle.Speed: given value exceeds type's boundaries"
+    );
+
+    let error = ClientError::from_entry(
+        SPEED,
+        entry_error(
+            400,
+            "type mismatch",
+            "cannot set existing datapoint to value of different type",
+        ),
+    );
+    assert!(matches!(error, ClientError::TypeMismatch { .. }));
+
+    // an unknown reason is classified by the code
+    let error = ClientError::from_entry(SPEED, entry_error(400, "unsupported type", "no"));
+    assert!(matches!(error, ClientError::TypeMismatch { .. }));
+
+    let error = ClientError::from_entry(SPEED, entry_error(403, "forbidden", "read only"));
+    assert_eq!(
+        error.to_string(),
+        "Vehicle.Speed: permission denied: read only"
+    );
+
+    let error = ClientError::from_entry(SPEED, entry_error(401, "unauthorized", "Unauthorized"));
+    assert!(matches!(error, ClientError::Auth(_)));
+    assert!(!error.is_retryable());
+
+    // kept as returned
+    let error = ClientError::from_entry(SPEED, entry_error(500, "internal", "broken"));
+    assert_eq!(
+        error.to_string(),
+        "databroker returned errors: 500 internal: broken"
+    );
+    assert_eq!(error.path(), None);
+}
+
+#[test]
+fn maps_status_codes() {
+    let error = ClientError::from(Status::unavailable("restarting"));
+    assert!(matches!(error, ClientError::Unavailable(_)));
+    assert!(error.is_retryable());
+    assert_eq!(error.to_string(), "databroker unavailable: restarting");
+    let source = error.source().unwrap().downcast_ref::<Status>().unwrap();
+    assert_eq!(source.code(), Code::Unavailable);
+
+    let error = ClientError::from(Status::deadline_exceeded("too slow"));
+    assert!(matches!(error, ClientError::Timeout { path: None, .. }));
+    assert!(error.is_retryable());
+
+    let error = ClientError::from(Status::permission_denied("read only"));
+    assert!(matches!(
+        error,
+        ClientError::PermissionDenied { path: None, .. }
+    ));
+    assert!(!error.is_retryable());
+
+    let error = ClientError::from(Status::unauthenticated("expired"));
+    assert!(matches!(error, ClientError::Auth(_)));
+    assert!(!error.is_retryable());
+    assert!(error.source().is_some());
+
+    assert!(!ClientError::Parse("Parse Float error".to_string()).is_retryable());
+}
+
+#[tokio::test]
+async fn reports_the_path_of_single_calls() {
+    let databroker = common::databroker();
+    let server = databroker.serve().unwrap();
+    let client = server.client().with_restriction_check(false);
+
+    match client.set_target_value(SPEED, "1").await {
+        Err(ClientError::NotActuator { path }) => assert_eq!(path, SPEED),
+        other => panic!("expected a sensor error, got {:?}", other),
+    }
+    // rejected by the databroker
+    match client.set_target_value(HEATING, "50").await {
+        Err(error @ ClientError::OutOfRange { .. }) => assert_eq!(error.path(), Some(HEATING)),
+        other => panic!("expected a restriction error, got {:?}", other),
+    }
+
+    databroker.fail_always(Method::Get, Status::permission_denied("no read access"));
+    match client.get_current_value(SPEED).await {
+        Err(error @ ClientError::PermissionDenied { .. }) => {
+            assert_eq!(error.path(), Some(SPEED))
+        }
+        other => panic!("expected a permission error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn retries_retryable_errors_once() {
+    let databroker = MockDatabroker::new()
+        .with_signal(SPEED, DataType::Float, EntryType::Sensor)
+        .with_value(SPEED, Value::Float(50.0));
+    let server = databroker.serve().unwrap();
+    let client = server.client();
+    client.connect().await.unwrap();
+
+    databroker.fail_next(Method::Get, Status::deadline_exceeded("too slow"));
+    assert!(client.get_current_value(SPEED).await.unwrap().is_some());
+
+    databroker.fail_always(Method::Get, Status::unavailable("restarting"));
+    match client.get_current_value(SPEED).await {
+        Err(error @ ClientError::Unavailable(_)) => assert!(error.is_retryable()),
+        other => panic!("expected the databroker to be unavailable, got {:?}", other),
+    }
+
+    databroker.fail_always(Method::Get, Status::internal("broken"));
+    match client.get_current_value(SPEED).await {
+        Err(error @ ClientError::Status(_)) => assert!(!error.is_retryable()),
+        other => panic!("expected the status, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn does_not_send_a_failed_set_again() {
+    let databroker = MockDatabroker::new()
+        .with_signal(SPEED, DataType::Float, EntryType::Sensor)
+        .with_value(SPEED, Value::Float(50.0));
+    let server = databroker.serve().unwrap();
+    let client = server.client();
+    client.connect().await.unwrap();
+
+    // the databroker may have applied it before the call failed
+    databroker.fail_next(Method::Set, Status::unavailable("restarting"));
+    match client.set_current_value(SPEED, "60.0").await {
+        Err(error @ ClientError::Unavailable(_)) => assert!(error.is_retryable()),
+        other => panic!("expected the databroker to be unavailable, got {:?}", other),
+    }
+    assert_eq!(databroker.calls(Method::Set), 1);
+
+    client.set_current_value(SPEED, "60.0").await.unwrap();
+    assert_eq!(databroker.calls(Method::Set), 2);
+}
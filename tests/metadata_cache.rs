@@ -0,0 +1,77 @@
+mod common;
+
+use std::time::Duration;
+
+use simple_kuksa_client::common::Value;
+use simple_kuksa_client::testing::Method;
+use simple_kuksa_client::KuksaClient;
+
+use common::databroker;
+
+const ROW1_LEFT: &str = "Vehicle.Cabin.Door.Row1.Left.IsOpen";
+const ROW1_RIGHT: &str = "Vehicle.Cabin.Door.Row1.Right.IsOpen";
+
+#[tokio::test]
+async fn reuses_metadata_for_actuator_commands() {
+    let databroker = databroker();
+    let server = databroker.serve().unwrap();
+    let client = KuksaClient::new(&server.url());
+
+    client.set_target_value(ROW1_LEFT, "true").await.unwrap();
+    assert_eq!(databroker.calls(Method::Get), 1);
+
+    client.set_target_value(ROW1_LEFT, "false").await.unwrap();
+    client.get_target_value(ROW1_LEFT).await.unwrap();
+    // only the value itself was read
+    assert_eq!(databroker.calls(Method::Get), 2);
+
+    client.invalidate_metadata("Vehicle.Cabin");
+    client.set_target_value(ROW1_LEFT, "true").await.unwrap();
+    assert_eq!(databroker.calls(Method::Get), 3);
+}
+
+#[tokio::test]
+async fn prefetches_a_branch() {
+    let databroker = databroker();
+    let server = databroker.serve().unwrap();
+    let client = KuksaClient::new(&server.url());
+
+    assert_eq!(
+        client
+            .prefetch_metadata("Vehicle.Cabin.Door")
+            .await
+            .unwrap(),
+        2
+    );
+    let calls = databroker.calls(Method::Get);
+
+    client
+        .batch_set_target_values(vec![
+            (ROW1_LEFT, Value::Bool(true)),
+            (ROW1_RIGHT, Value::Bool(true)),
+        ])
+        .await
+        .unwrap();
+    client.set_target_value(ROW1_RIGHT, "false").await.unwrap();
+    assert_eq!(databroker.calls(Method::Get), calls);
+}
+
+#[tokio::test]
+async fn expires_metadata_after_ttl() {
+    let databroker = databroker();
+    let server = databroker.serve().unwrap();
+    let client = KuksaClient::new(&server.url()).with_metadata_ttl(Some(Duration::from_millis(50)));
+
+    client.set_target_value(ROW1_LEFT, "true").await.unwrap();
+    client.set_target_value(ROW1_LEFT, "true").await.unwrap();
+    assert_eq!(databroker.calls(Method::Get), 1);
+
+    tokio::time::sleep(Duration::from_millis(60)).await;
+    client.set_target_value(ROW1_LEFT, "true").await.unwrap();
+    assert_eq!(databroker.calls(Method::Get), 2);
+
+    let uncached = KuksaClient::new(&server.url()).with_metadata_ttl(Some(Duration::ZERO));
+    uncached.set_target_value(ROW1_LEFT, "true").await.unwrap();
+    // is_actuator and the datatype lookup
+    assert_eq!(databroker.calls(Method::Get), 4);
+}
@@ -0,0 +1,171 @@
+mod common;
+
+use databroker_proto::sdv::databroker::v1::datapoint::Value;
+use databroker_proto::sdv::databroker::v1::{ChangeType, DataType, RegistrationMetadata};
+use simple_kuksa_client::common::ClientError;
+use simple_kuksa_client::{BrokerClient, Provider};
+use tokio_stream::StreamExt;
+use tonic::Status;
+
+use common::broker::BrokerStandIn;
+
+const SPEED: &str = "Vehicle.Speed";
+const ODOMETER: &str = "Vehicle.TraveledDistance";
+
+fn registration(name: &str, data_type: DataType, change_type: ChangeType) -> RegistrationMetadata {
+    RegistrationMetadata {
+        name: name.to_string(),
+        data_type: data_type.into(),
+        description: String::new(),
+        change_type: change_type.into(),
+    }
+}
+
+#[tokio::test]
+async fn registers_and_updates_by_path() {
+    let stand_in = BrokerStandIn::new();
+    let server = stand_in.serve(None);
+    let provider = Provider::new(&server.url());
+    let client = BrokerClient::new(&server.url());
+
+    let ids = provider
+        .register_datapoints(vec![
+            registration(SPEED, DataType::Float, ChangeType::Continuous),
+            registration(ODOMETER, DataType::Uint32, ChangeType::OnChange),
+        ])
+        .await
+        .unwrap();
+    assert_eq!(provider.id(SPEED), Some(ids[SPEED]));
+    assert_eq!(provider.id(ODOMETER), Some(ids[ODOMETER]));
+
+    provider
+        .update_datapoints(vec![
+            (SPEED, Value::FloatValue(72.5)),
+            (ODOMETER, Value::Uint32Value(1200)),
+        ])
+        .await
+        .unwrap();
+
+    let datapoints = client.get_datapoints(vec![SPEED, ODOMETER]).await.unwrap();
+    assert_eq!(datapoints[SPEED].value, Some(Value::FloatValue(72.5)));
+    assert_eq!(datapoints[ODOMETER].value, Some(Value::Uint32Value(1200)));
+
+    let metadata = client.get_metadata(vec![SPEED]).await.unwrap();
+    assert_eq!(metadata[0].change_type, ChangeType::Continuous as i32);
+}
+
+#[tokio::test]
+async fn clones_share_the_registered_ids() {
+    let server = BrokerStandIn::new().serve(None);
+    let provider = Provider::new(&server.url());
+    let feeder = provider.clone();
+
+    let id = provider
+        .register_datapoint(SPEED, DataType::Float, ChangeType::Continuous, "")
+        .await
+        .unwrap();
+    assert_eq!(feeder.id(SPEED), Some(id));
+
+    feeder
+        .update_datapoints(vec![(SPEED, Value::FloatValue(31.0))])
+        .await
+        .unwrap();
+    let client = BrokerClient::new(&server.url());
+    let datapoints = client.get_datapoints(vec![SPEED]).await.unwrap();
+    assert_eq!(datapoints[SPEED].value, Some(Value::FloatValue(31.0)));
+}
+
+#[tokio::test]
+async fn does_not_send_a_failed_update_again() {
+    let stand_in = BrokerStandIn::new();
+    let server = stand_in.serve(None);
+    let provider = Provider::new(&server.url());
+    provider
+        .register_datapoint(SPEED, DataType::Float, ChangeType::Continuous, "")
+        .await
+        .unwrap();
+
+    // the databroker may have applied it before the call failed
+    stand_in.fail_next("UpdateDatapoints", Status::unavailable("restarting"));
+    let result = provider
+        .update_datapoints(vec![(SPEED, Value::FloatValue(60.0))])
+        .await;
+    assert!(matches!(result, Err(ClientError::Unavailable(_))));
+    assert_eq!(stand_in.calls("UpdateDatapoints"), 1);
+
+    provider
+        .update_datapoints(vec![(SPEED, Value::FloatValue(60.0))])
+        .await
+        .unwrap();
+    assert_eq!(stand_in.calls("UpdateDatapoints"), 2);
+}
+
+#[tokio::test]
+async fn reports_errors_by_path() {
+    let server = BrokerStandIn::new().serve(None);
+    let provider = Provider::new(&server.url());
+    provider
+        .register_datapoint(SPEED, DataType::Float, ChangeType::Continuous, "")
+        .await
+        .unwrap();
+
+    // not registered: nothing is sent
+    match provider
+        .update_datapoints(vec![
+            (SPEED, Value::FloatValue(1.0)),
+            (ODOMETER, Value::Uint32Value(1)),
+        ])
+        .await
+    {
+        Err(ClientError::Entries(errors)) => {
+            assert_eq!(errors.len(), 1);
+            assert!(matches!(errors[ODOMETER], ClientError::NotFound { .. }));
+        }
+        other => panic!("expected errors by path, got {:?}", other),
+    }
+
+    // rejected by the databroker, the id is mapped back to the path
+    match provider
+        .update_datapoints(vec![(SPEED, Value::BoolValue(true))])
+        .await
+    {
+        Err(ClientError::Entries(errors)) => {
+            assert!(matches!(errors[SPEED], ClientError::TypeMismatch { .. }));
+        }
+        other => panic!("expected errors by path, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn streams_updates_by_path() {
+    let server = BrokerStandIn::new().serve(None);
+    let provider = Provider::new(&server.url());
+    let client = BrokerClient::new(&server.url());
+    provider
+        .register_datapoint(SPEED, DataType::Float, ChangeType::Continuous, "")
+        .await
+        .unwrap();
+
+    let (updates, mut errors) = provider.datapoint_streamer(8).await.unwrap();
+
+    updates
+        .send(vec![(SPEED.to_string(), Value::FloatValue(10.0))])
+        .await
+        .unwrap();
+    updates
+        .send(vec![
+            (SPEED.to_string(), Value::StringValue("fast".to_string())),
+            (ODOMETER.to_string(), Value::Uint32Value(1)),
+        ])
+        .await
+        .unwrap();
+
+    // the unregistered path is reported locally, the invalid value by the databroker
+    let mut rejected = errors.next().await.unwrap().unwrap();
+    rejected.extend(errors.next().await.unwrap().unwrap());
+    assert!(matches!(rejected[ODOMETER], ClientError::NotFound { .. }));
+    assert!(matches!(rejected[SPEED], ClientError::TypeMismatch { .. }));
+
+    let datapoints = client.get_datapoints(vec![SPEED]).await.unwrap();
+    assert_eq!(datapoints[SPEED].value, Some(Value::FloatValue(10.0)));
+}
@@ -0,0 +1,89 @@
+mod common;
+
+use databroker_proto::sdv::databroker::v1::datapoint::Value;
+use databroker_proto::sdv::databroker::v1::{DataType, EntryType};
+use simple_kuksa_client::common::ClientError;
+use simple_kuksa_client::{BrokerClient, Condition, Query};
+use tokio_stream::StreamExt;
+
+use common::broker::BrokerStandIn;
+
+const SPEED: &str = "Vehicle.Speed";
+const ABS: &str = "Vehicle.ADAS.ABS.IsActive";
+const GEAR: &str = "Vehicle.Powertrain.Transmission.SelectedGear";
+const DRIVER: &str = "Vehicle.Driver.Identifier.Subject";
+
+#[test]
+fn builds_query_strings() {
+    assert_eq!(
+        Query::select([SPEED, ABS]).build().unwrap(),
+        "SELECT Vehicle.Speed, Vehicle.ADAS.ABS.IsActive"
+    );
+
+    let query = Query::select([SPEED])
+        .filter(Condition::gt(SPEED, 50.0))
+        .filter(Condition::eq(ABS, true).or(Condition::lt(GEAR, 0).not()));
+    assert_eq!(
+        query.build().unwrap(),
+        "SELECT Vehicle.Speed WHERE Vehicle.Speed > 50.0 \
+         AND (Vehicle.ADAS.ABS.IsActive = true OR NOT (Vehicle.Powertrain.Transmission.SelectedGear < 0))"
+    );
+
+    assert_eq!(
+        Query::select([SPEED])
+            .filter(Condition::not_eq(DRIVER, "O'Brien"))
+            .build()
+            .unwrap(),
+        "SELECT Vehicle.Speed WHERE Vehicle.Driver.Identifier.Subject <> 'O''Brien'"
+    );
+}
+
+#[test]
+fn rejects_invalid_queries() {
+    let no_fields = Query::select(Vec::<String>::new());
+    let wildcard = Query::select(["Vehicle.*"]);
+    let empty_segment = Query::select([SPEED]).filter(Condition::gt("Vehicle..Speed", 1));
+    let not_a_number = Query::select([SPEED]).filter(Condition::gt(SPEED, f64::NAN));
+
+    for query in [no_fields, wildcard, empty_segment, not_a_number] {
+        assert!(matches!(query.build(), Err(ClientError::Parse(_))));
+    }
+}
+
+#[tokio::test]
+async fn subscribes_with_a_typed_stream() {
+    let stand_in = BrokerStandIn::new()
+        .with_datapoint(SPEED, DataType::Float, EntryType::Sensor)
+        .with_datapoint(ABS, DataType::Bool, EntryType::Actuator);
+    let server = stand_in.serve(None);
+    let client = BrokerClient::new(&server.url());
+    let feeder = client.clone();
+
+    let query = Query::select([SPEED, ABS]).filter(Condition::gt(SPEED, 50.0));
+    let mut updates = client.subscribe_query(&query).await.unwrap();
+    assert_eq!(
+        stand_in.queries.lock().unwrap().as_slice(),
+        [query.build().unwrap()]
+    );
+
+    feeder
+        .set_datapoints(vec![(SPEED, Value::FloatValue(88.0))])
+        .await
+        .unwrap();
+
+    let fields = updates.next().await.unwrap().unwrap();
+    assert_eq!(fields[SPEED].value, Some(Value::FloatValue(88.0)));
+    // not set yet
+    assert_eq!(fields[ABS].value, None);
+}
+
+#[tokio::test]
+async fn does_not_send_invalid_queries() {
+    let stand_in = BrokerStandIn::new();
+    let server = stand_in.serve(None);
+    let client = BrokerClient::new(&server.url());
+
+    let result = client.subscribe_query(&Query::select(["Vehicle.*"])).await;
+    assert!(matches!(result, Err(ClientError::Parse(_))));
+    assert!(stand_in.queries.lock().unwrap().is_empty());
+}
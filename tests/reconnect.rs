@@ -0,0 +1,123 @@
+mod common;
+
+use std::time::Duration;
+
+use simple_kuksa_client::common::{value_from_datapoint, ClientError, Value};
+use simple_kuksa_client::testing::Method;
+use simple_kuksa_client::{BackoffPolicy, ConnectionState, KuksaClient};
+use tonic::Status;
+
+use common::databroker;
+
+const SPEED: &str = "Vehicle.Speed";
+
+fn quick_backoff() -> BackoffPolicy {
+    BackoffPolicy::new()
+        .initial_delay(Duration::from_millis(20))
+        .max_delay(Duration::from_millis(100))
+        .max_retries(Some(50))
+}
+
+#[tokio::test]
+async fn connects_on_first_use() {
+    let server = databroker().serve().unwrap();
+    let client = KuksaClient::new(&server.url());
+    assert_eq!(client.connection_state(), ConnectionState::Disconnected);
+
+    client.set_current_value(SPEED, "12.5").await.unwrap();
+    assert_eq!(client.connection_state(), ConnectionState::Connected);
+    let datapoint = client.get_current_value(SPEED).await.unwrap();
+    assert_eq!(value_from_datapoint(datapoint), Some(Value::Float(12.5)));
+}
+
+#[tokio::test]
+async fn reconnects_after_databroker_restart() {
+    let databroker = databroker();
+    let server = databroker.serve().unwrap();
+    let address = server.address();
+
+    let client = KuksaClient::new(&server.url()).with_backoff(quick_backoff());
+    let states = client.watch_connection_state();
+    client.set_current_value(SPEED, "1.5").await.unwrap();
+
+    server.stop();
+    let restart = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        databroker.serve_on(address).unwrap()
+    });
+
+    let datapoint = client.get_current_value(SPEED).await.unwrap();
+    assert_eq!(value_from_datapoint(datapoint), Some(Value::Float(1.5)));
+    assert_eq!(client.connection_state(), ConnectionState::Connected);
+    assert!(states.has_changed().unwrap());
+
+    let _server = restart.await.unwrap();
+}
+
+#[tokio::test]
+async fn reports_reconnecting_state() {
+    let databroker = databroker();
+    let server = databroker.serve().unwrap();
+    let address = server.address();
+
+    let client = KuksaClient::new(&server.url()).with_backoff(quick_backoff());
+    client.connect().await.unwrap();
+    let mut states = client.watch_connection_state();
+    server.stop();
+
+    let observer = tokio::spawn(async move {
+        loop {
+            states.changed().await.unwrap();
+            let state = *states.borrow_and_update();
+            if let ConnectionState::Reconnecting { attempt } = state {
+                if attempt > 0 {
+                    return state;
+                }
+            }
+        }
+    });
+
+    let call = tokio::spawn(async move {
+        let result = client.get_current_value(SPEED).await;
+        (client, result)
+    });
+
+    observer.await.unwrap();
+    let _server = databroker.serve_on(address).unwrap();
+    let (client, result) = call.await.unwrap();
+    assert!(result.unwrap().is_none());
+    assert_eq!(client.connection_state(), ConnectionState::Connected);
+}
+
+#[tokio::test]
+async fn gives_up_after_max_retries() {
+    let server = databroker().serve().unwrap();
+    let url = server.url();
+    server.stop();
+
+    let backoff = BackoffPolicy::new()
+        .initial_delay(Duration::from_millis(10))
+        .max_retries(Some(2));
+    let client = KuksaClient::new(&url).with_backoff(backoff);
+
+    assert!(matches!(
+        client.get_current_value(SPEED).await,
+        Err(ClientError::Connection(_))
+    ));
+    assert_eq!(client.connection_state(), ConnectionState::Disconnected);
+}
+
+#[tokio::test]
+async fn retries_a_timeout_on_the_same_channel() {
+    let databroker = databroker();
+    let server = databroker.serve().unwrap();
+    let client = server.client();
+    client.connect().await.unwrap();
+    let connections = databroker.calls(Method::GetServerInfo);
+
+    databroker.fail_next(Method::Get, Status::deadline_exceeded("slow"));
+    client.get_current_value(SPEED).await.unwrap();
+
+    assert_eq!(databroker.calls(Method::Get), 2);
+    assert_eq!(databroker.calls(Method::GetServerInfo), connections);
+}
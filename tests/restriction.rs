@@ -0,0 +1,130 @@
+mod common;
+
+use databroker_proto::kuksa::val::v1::value_restriction::Type;
+use databroker_proto::kuksa::val::v1::{
+    ValueRestrictionFloat, ValueRestrictionString, ValueRestrictionUint,
+};
+use simple_kuksa_client::common::{ClientError, Value};
+use simple_kuksa_client::testing::MockDatabroker;
+use simple_kuksa_client::KuksaClient;
+
+const SPEED: &str = "Vehicle.Speed";
+const POSITION: &str = "Vehicle.Cabin.Seat.Row1.Pos1.Position";
+const MODE: &str = "Vehicle.Powertrain.Transmission.PerformanceMode";
+
+fn databroker() -> MockDatabroker {
+    common::databroker()
+        .with_restriction(
+            SPEED,
+            Type::FloatingPoint(ValueRestrictionFloat {
+                min: Some(0.0),
+                max: Some(250.5),
+                allowed_values: vec![],
+            }),
+        )
+        .with_restriction(
+            POSITION,
+            Type::Unsigned(ValueRestrictionUint {
+                min: Some(100),
+                max: Some(500),
+                allowed_values: vec![],
+            }),
+        )
+        .with_restriction(
+            MODE,
+            Type::String(ValueRestrictionString {
+                allowed_values: vec!["NORMAL".to_string(), "SPORT".to_string()],
+            }),
+        )
+}
+
+fn violation(result: Result<(), ClientError>) -> String {
+    match result {
+        Err(error @ ClientError::OutOfRange { .. }) => error.to_string(),
+        other => panic!("expected an out of bounds error, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn rejects_values_outside_the_restriction() {
+    let server = databroker().serve().unwrap();
+    let client = KuksaClient::new(&server.url());
+
+    assert_eq!(
+        violation(client.set_current_value(SPEED, "300").await),
+        "Vehicle.Speed: 300 is above the maximum 250.5"
+    );
+    assert_eq!(
+        violation(client.set_target_value(POSITION, "50").await),
+        "Vehicle.Cabin.Seat.Row1.Pos1.Position: 50 is below the minimum 100"
+    );
+    assert_eq!(
+        violation(client.set_target_value(MODE, "RACE").await),
+        "Vehicle.Powertrain.Transmission.PerformanceMode: RACE is not one of the allowed values [NORMAL, SPORT]"
+    );
+
+    // the bounds themselves are allowed
+    client.set_current_value(SPEED, "250.5").await.unwrap();
+    client.set_target_value(POSITION, "100").await.unwrap();
+    client.set_target_value(MODE, "SPORT").await.unwrap();
+}
+
+#[tokio::test]
+async fn checks_batches_and_typed_signals() {
+    let server = databroker().serve().unwrap();
+    let client = KuksaClient::new(&server.url());
+
+    match client
+        .batch_set_target_values(vec![
+            (POSITION, Value::Uint32(600)),
+            (MODE, Value::String("NORMAL".to_string())),
+        ])
+        .await
+    {
+        Err(ClientError::Entries(errors)) => {
+            assert_eq!(errors.len(), 1);
+            assert!(matches!(errors[POSITION], ClientError::OutOfRange { .. }));
+        }
+        other => panic!("expected errors by path, got {:?}", other),
+    }
+    // nothing was sent
+    assert_eq!(client.get_target_value(MODE).await.unwrap(), None);
+
+    let speed = client.signal::<f32>(SPEED).await.unwrap();
+    assert!(violation(speed.set(-1.0).await).ends_with("-1 is below the minimum 0"));
+    speed.set(88.5).await.unwrap();
+}
+
+#[tokio::test]
+async fn can_leave_the_checks_to_the_databroker() {
+    let server = databroker().serve().unwrap();
+    let client = KuksaClient::new(&server.url()).with_restriction_check(false);
+
+    // rejected by the databroker, not by the client-side validation
+    match client.set_current_value(SPEED, "300").await {
+        Err(ClientError::OutOfRange { path, message }) => {
+            assert_eq!(path, SPEED);
+            assert_eq!(message, "given value exceeds type's boundaries");
+        }
+        other => panic!(
+            "expected the databroker to reject the value, got {:?}",
+            other
+        ),
+    }
+    match client
+        .batch_set_target_values(vec![(POSITION, Value::Uint32(600))])
+        .await
+    {
+        Err(ClientError::Entries(errors)) => match &errors[POSITION] {
+            ClientError::OutOfRange { message, .. } => {
+                assert_eq!(message, "given value exceeds type's boundaries")
+            }
+            other => panic!("expected the value to be out of range, got {:?}", other),
+        },
+        other => panic!(
+            "expected the databroker to reject the value, got {:?}",
+            other
+        ),
+    }
+    client.set_current_value(SPEED, "250.5").await.unwrap();
+}
@@ -0,0 +1,64 @@
+mod common;
+
+use simple_kuksa_client::common::ClientError;
+use simple_kuksa_client::testing::MockDatabroker;
+use simple_kuksa_client::{ConnectionState, KuksaClient, ServerFeature, ServerInfo};
+
+const SPEED: &str = "Vehicle.Speed";
+
+fn databroker(version: &str) -> MockDatabroker {
+    common::databroker().with_version(version)
+}
+
+#[tokio::test]
+async fn reports_server_name_and_version() {
+    let server = databroker("0.4.1").serve().unwrap();
+    let client = KuksaClient::new(&server.url());
+
+    let server_info = client.server_info().await.unwrap();
+    assert_eq!(server_info.name, "databroker");
+    assert_eq!(server_info.semver(), Some((0, 4, 1)));
+    assert!(server_info.supports(ServerFeature::StreamedUpdate));
+}
+
+#[tokio::test]
+async fn refuses_too_old_databroker() {
+    let server = databroker("0.2.9").serve().unwrap();
+    let client = KuksaClient::new(&server.url());
+
+    assert!(matches!(
+        client.connect().await,
+        Err(ClientError::Incompatible(_))
+    ));
+    assert_eq!(client.connection_state(), ConnectionState::Disconnected);
+
+    let unchecked = KuksaClient::new(&server.url()).with_version_check(false);
+    unchecked.connect().await.unwrap();
+    assert!(unchecked.get_current_value(SPEED).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn refuses_streamed_update_on_old_databroker() {
+    let server = databroker("0.3.0").serve().unwrap();
+    let client = KuksaClient::new(&server.url());
+
+    client.connect().await.unwrap();
+    assert!(matches!(
+        client.streamed_update(tokio_stream::empty()).await,
+        Err(ClientError::Incompatible(_))
+    ));
+}
+
+#[test]
+fn parses_databroker_versions() {
+    let server_info = |version: &str| ServerInfo {
+        name: "databroker".to_string(),
+        version: version.to_string(),
+    };
+
+    assert_eq!(server_info("0.4.1-dev.0").semver(), Some((0, 4, 1)));
+    assert_eq!(server_info("v0.5").semver(), Some((0, 5, 0)));
+    assert_eq!(server_info("main").semver(), None);
+    assert!(server_info("main").is_compatible());
+    assert!(!server_info("0.4.0").supports(ServerFeature::StreamedUpdate));
+}
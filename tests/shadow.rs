@@ -0,0 +1,108 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use simple_kuksa_client::common::{ClientError, Value};
+use simple_kuksa_client::testing::MockDatabroker;
+use tonic::Status;
+
+const SPEED: &str = "Vehicle.Speed";
+const LEFT: &str = "Vehicle.Cabin.Door.Row1.Left.IsOpen";
+const RIGHT: &str = "Vehicle.Cabin.Door.Row1.Right.IsOpen";
+
+fn databroker() -> MockDatabroker {
+    common::databroker()
+        .with_value(SPEED, Value::Float(30.0))
+        .with_value(LEFT, Value::Bool(false))
+}
+
+#[tokio::test]
+async fn mirrors_signals_and_branches() {
+    let databroker = databroker();
+    let server = databroker.serve().unwrap();
+    let client = server.client();
+
+    let shadow = client
+        .shadow(&[SPEED, "Vehicle.Cabin.Door.*"])
+        .await
+        .unwrap();
+    assert_eq!(shadow.paths(), vec![LEFT, RIGHT, SPEED]);
+    assert!(shadow.is_live());
+
+    // served from memory
+    databroker.set_latency(Duration::from_millis(200));
+    let start = Instant::now();
+    assert_eq!(shadow.value(SPEED), Some(Value::Float(30.0)));
+    assert_eq!(shadow.value(LEFT), Some(Value::Bool(false)));
+    // no value yet
+    assert_eq!(shadow.get(RIGHT), None);
+    assert_eq!(shadow.get("Vehicle.Unknown"), None);
+    assert!(start.elapsed() < Duration::from_millis(100));
+
+    let mut right = shadow.watch(RIGHT).unwrap();
+    databroker.set_value(RIGHT, Value::Bool(true));
+    right.changed().await.unwrap();
+    let entry = right.borrow().clone().unwrap();
+    assert_eq!(entry.value(), Some(&Value::Bool(true)));
+    assert_eq!(shadow.value(RIGHT), Some(Value::Bool(true)));
+    assert!(shadow.watch("Vehicle.Unknown").is_none());
+}
+
+#[tokio::test]
+async fn reports_staleness() {
+    let databroker = databroker();
+    let server = databroker.serve().unwrap();
+    let client = server.client();
+    let shadow = client.shadow(&[SPEED]).await.unwrap();
+
+    assert!(shadow.is_fresh(SPEED, Duration::from_secs(1)));
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(shadow.get(SPEED).unwrap().age() >= Duration::from_millis(50));
+    assert!(!shadow.is_fresh(SPEED, Duration::from_millis(10)));
+
+    // the values are kept while the subscription is lost, and refreshed after resuming
+    let mut live = shadow.watch_live();
+    databroker.close_subscriptions(Status::unavailable("restarting"));
+    live.wait_for(|live| !*live).await.unwrap();
+    assert_eq!(shadow.value(SPEED), Some(Value::Float(30.0)));
+
+    live.wait_for(|live| *live).await.unwrap();
+    let mut speed = shadow.watch(SPEED).unwrap();
+    databroker.set_value(SPEED, Value::Float(50.0));
+    speed
+        .wait_for(|entry| {
+            entry.as_ref().and_then(|entry| entry.value()) == Some(&Value::Float(50.0))
+        })
+        .await
+        .unwrap();
+    assert!(shadow.is_fresh(SPEED, Duration::from_secs(1)));
+}
+
+#[tokio::test]
+async fn rejects_unknown_paths() {
+    let server = databroker().serve().unwrap();
+    let client = server.client();
+
+    assert!(matches!(
+        client.shadow(&[SPEED, "Vehicle.Unknown"]).await,
+        Err(ClientError::NotFound { .. })
+    ));
+}
+
+#[tokio::test]
+async fn is_live_again_once_the_values_are_refreshed() {
+    let databroker = databroker();
+    let server = databroker.serve().unwrap();
+    let client = server.client();
+    let shadow = client.shadow(&[SPEED]).await.unwrap();
+
+    // resubscribing and fetching the latest values take a while
+    databroker.set_latency(Duration::from_millis(100));
+    let mut live = shadow.watch_live();
+    databroker.close_subscriptions(Status::unavailable("restarting"));
+    let interrupted = Instant::now();
+    live.wait_for(|live| !*live).await.unwrap();
+
+    live.wait_for(|live| *live).await.unwrap();
+    assert!(shadow.get(SPEED).unwrap().age() < interrupted.elapsed());
+}
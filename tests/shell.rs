@@ -0,0 +1,119 @@
+mod common;
+
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, Command, Stdio};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use simple_kuksa_client::KuksaClient;
+
+use common::databroker;
+
+const SPEED: &str = "Vehicle.Speed";
+const DOOR: &str = "Vehicle.Cabin.Door.Row1.Left.IsOpen";
+
+// stdin is not a terminal: lines are read without prompt or line editing
+fn shell(address: &str) -> Child {
+    Command::new(env!("CARGO_BIN_EXE_kuksa-cli"))
+        .env("KUKSA_ADDRESS", address)
+        .env_remove("KUKSA_TOKEN")
+        .env_remove("KUKSA_TOKEN_FILE")
+        .env_remove("KUKSA_CA_CERT")
+        .arg("shell")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap()
+}
+
+// the stdout lines as they are printed
+fn lines(child: &mut Child) -> mpsc::Receiver<String> {
+    let stdout = child.stdout.take().unwrap();
+    let (sender, receiver) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines() {
+            if sender.send(line.unwrap()).is_err() {
+                return;
+            }
+        }
+    });
+    receiver
+}
+
+fn wait_for_line(lines: &mpsc::Receiver<String>, prefix: &str) -> String {
+    loop {
+        let line = lines
+            .recv_timeout(Duration::from_secs(5))
+            .unwrap_or_else(|_| panic!("no line starting with {:?}", prefix));
+        if line.starts_with(prefix) {
+            return line;
+        }
+    }
+}
+
+#[test]
+fn runs_commands_on_one_connection() {
+    let server = databroker().serve().unwrap();
+    let mut child = shell(&server.url());
+
+    let mut stdin = child.stdin.take().unwrap();
+    writeln!(stdin, "set Vehicle.Speed=88.5 {}=true", DOOR).unwrap();
+    writeln!(stdin, "get {} {}", SPEED, DOOR).unwrap();
+    writeln!(stdin, "set Vehicle.Speed=fast").unwrap();
+    writeln!(stdin, "metadata {}", SPEED).unwrap();
+    writeln!(stdin, "exit").unwrap();
+
+    let output = child.wait_with_output().unwrap();
+    assert!(output.status.success(), "{:?}", output);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 3, "{}", stdout);
+    assert!(lines[0].starts_with(&format!("{}: true (", DOOR)));
+    assert!(lines[1].starts_with(&format!("{}: 88.5 (", SPEED)));
+    assert_eq!(
+        lines[2],
+        format!("{}: Float Sensor [km/h] Vehicle speed.", SPEED)
+    );
+
+    // a failed command does not end the shell
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("Parse Float error"), "{}", stderr);
+}
+
+#[tokio::test]
+async fn prints_background_subscriptions() {
+    let databroker = databroker();
+    let server = databroker.serve().unwrap();
+    let client = KuksaClient::new(&server.url());
+    let mut child = shell(&server.url());
+    let lines = lines(&mut child);
+    let mut stdin = child.stdin.take().unwrap();
+
+    writeln!(stdin, "subscribe {}", SPEED).unwrap();
+    wait_for_line(&lines, "subscription 1 started");
+    databroker.wait_for_subscriber().await;
+
+    // the current value (none yet) comes first
+    wait_for_line(&lines, "[1] ");
+    client.set_current_value(SPEED, "42").await.unwrap();
+    let update = wait_for_line(&lines, "[1] ");
+    assert!(
+        update.starts_with(&format!("[1] {}: 42 (", SPEED)),
+        "{}",
+        update
+    );
+
+    writeln!(stdin, "subscriptions").unwrap();
+    assert_eq!(wait_for_line(&lines, "[1] "), format!("[1] {}", SPEED));
+
+    writeln!(stdin, "unsubscribe 1").unwrap();
+    writeln!(stdin, "subscriptions").unwrap();
+    writeln!(stdin, "server-info").unwrap();
+    // nothing listed between the unsubscribe and the server info
+    let line = lines.recv_timeout(Duration::from_secs(5)).unwrap();
+    assert!(line.starts_with("databroker"), "{}", line);
+
+    drop(stdin);
+    assert!(child.wait().unwrap().success());
+}
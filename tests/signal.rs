@@ -0,0 +1,92 @@
+mod common;
+
+use std::time::{Duration, SystemTime};
+
+use databroker_proto::kuksa::val::v1::{DataType, EntryType};
+use simple_kuksa_client::common::ClientError;
+use simple_kuksa_client::testing::MockDatabroker;
+use simple_kuksa_client::KuksaClient;
+use tokio_stream::StreamExt;
+
+const SPEED: &str = "Vehicle.Speed";
+const DOOR: &str = "Vehicle.Cabin.Door.Row1.Left.IsOpen";
+const POSITIONS: &str = "Vehicle.Cabin.Seat.Row1.Pos1.Positions";
+const SERVICE: &str = "Vehicle.Service.LastServiceDate";
+
+// the arrays and timestamps are not in tests/data/vss.json
+fn databroker() -> MockDatabroker {
+    common::databroker()
+        .with_signal(POSITIONS, DataType::Uint8Array, EntryType::Actuator)
+        .with_signal(SERVICE, DataType::Timestamp, EntryType::Attribute)
+}
+
+#[tokio::test]
+async fn gets_and_sets_typed_values() {
+    let server = databroker().serve().unwrap();
+    let client = KuksaClient::new(&server.url());
+
+    let speed = client.signal::<f32>(SPEED).await.unwrap();
+    assert_eq!(speed.get().await.unwrap(), None);
+    speed.set(88.5).await.unwrap();
+    assert_eq!(speed.get().await.unwrap(), Some(88.5));
+
+    let door = client.signal::<bool>(DOOR).await.unwrap();
+    door.set_target(true).await.unwrap();
+    assert_eq!(door.get_target().await.unwrap(), Some(true));
+    assert_eq!(door.get().await.unwrap(), None);
+
+    let positions = client.signal::<Vec<u8>>(POSITIONS).await.unwrap();
+    positions.set(vec![0, 128, 255]).await.unwrap();
+    assert_eq!(positions.get().await.unwrap(), Some(vec![0, 128, 255]));
+
+    // whole microseconds, the timestamp survives the round trip exactly
+    let date = SystemTime::UNIX_EPOCH + Duration::from_micros(1_714_564_800_000_000);
+    let service = client.signal::<SystemTime>(SERVICE).await.unwrap();
+    service.set(date).await.unwrap();
+    assert_eq!(service.get().await.unwrap(), Some(date));
+}
+
+#[tokio::test]
+async fn checks_the_datatype_once() {
+    let server = databroker().serve().unwrap();
+    let client = KuksaClient::new(&server.url());
+
+    match client.signal::<f64>(SPEED).await {
+        Err(ClientError::TypeMismatch { path, message }) => {
+            assert_eq!(path, SPEED);
+            assert!(message.contains("Float"), "{}", message);
+        }
+        Err(error) => panic!("expected a datatype error, got {:?}", error),
+        Ok(_) => panic!("expected a datatype error"),
+    }
+
+    assert!(matches!(
+        client.signal::<f32>("Vehicle.Unknown").await,
+        Err(ClientError::NotFound { .. })
+    ));
+
+    // a sensor has no target
+    let speed = client.signal::<f32>(SPEED).await.unwrap();
+    assert!(matches!(
+        speed.set_target(1.0).await,
+        Err(ClientError::NotActuator { .. })
+    ));
+}
+
+#[tokio::test]
+async fn streams_typed_updates() {
+    let databroker = databroker();
+    let server = databroker.serve().unwrap();
+    let client = KuksaClient::new(&server.url());
+
+    let speed = client.signal::<f32>(SPEED).await.unwrap();
+    let mut updates = speed.subscribe().await.unwrap();
+    databroker.wait_for_subscriber().await;
+
+    let writer = speed.clone();
+    writer.set(10.0).await.unwrap();
+    writer.set(20.5).await.unwrap();
+
+    assert_eq!(updates.next().await.unwrap().unwrap(), 10.0);
+    assert_eq!(updates.next().await.unwrap().unwrap(), 20.5);
+}
@@ -0,0 +1,64 @@
+use std::time::Duration;
+
+use databroker_proto::kuksa::val::v1::{
+    DataEntry, DataType, Datapoint, EntryType, EntryUpdate, Field,
+};
+use simple_kuksa_client::common::{value_from_datapoint, Value};
+use simple_kuksa_client::testing::MockDatabroker;
+use simple_kuksa_client::KuksaClient;
+use tokio_stream::StreamExt;
+
+const SPEED: &str = "Vehicle.Speed";
+
+fn speed_update(path: &str, speed: f32) -> EntryUpdate {
+    EntryUpdate {
+        fields: vec![Field::Value.into()],
+        entry: Some(DataEntry {
+            path: path.to_string(),
+            value: Some(Datapoint {
+                timestamp: None,
+                value: Some(Value::Float(speed)),
+            }),
+            metadata: None,
+            actuator_target: None,
+        }),
+    }
+}
+
+#[tokio::test]
+async fn forwards_batches_over_one_stream() {
+    let databroker = MockDatabroker::new().with_signal(SPEED, DataType::Float, EntryType::Sensor);
+    let server = databroker.serve().unwrap();
+    let client = KuksaClient::new(&server.url());
+
+    let (updates, mut errors) = client.streamed_updater(8).await.unwrap();
+
+    for speed in [10.0, 20.0, 30.0] {
+        updates
+            .send(vec![speed_update(SPEED, speed)])
+            .await
+            .unwrap();
+    }
+    updates
+        .send(vec![
+            speed_update(SPEED, 40.0),
+            speed_update("Vehicle.Unknown", 1.0),
+        ])
+        .await
+        .unwrap();
+
+    let rejected = tokio::time::timeout(Duration::from_secs(5), errors.next())
+        .await
+        .unwrap()
+        .unwrap()
+        .unwrap();
+    assert_eq!(rejected.len(), 1);
+    assert_eq!(rejected[0].path, "Vehicle.Unknown");
+
+    // the rejected batch is not applied
+    let datapoint = client.get_current_value(SPEED).await.unwrap();
+    assert_eq!(value_from_datapoint(datapoint), Some(Value::Float(30.0)));
+
+    drop(updates);
+    assert!(errors.next().await.is_none());
+}
@@ -0,0 +1,124 @@
+mod common;
+
+use std::time::Duration;
+
+use databroker_proto::kuksa::val::v1::{Field, SubscribeEntry, View};
+use simple_kuksa_client::common::{value_from_datapoint, ClientError, Value};
+use simple_kuksa_client::{
+    BackoffPolicy, KuksaClient, ManagedSubscription, SubscriptionEvent, SubscriptionOptions,
+    TokenProvider,
+};
+
+use common::databroker;
+
+const SPEED: &str = "Vehicle.Speed";
+
+fn quick_backoff() -> BackoffPolicy {
+    BackoffPolicy::new()
+        .initial_delay(Duration::from_millis(20))
+        .max_delay(Duration::from_millis(100))
+        .max_retries(None)
+}
+
+fn speed_entry() -> Vec<SubscribeEntry> {
+    vec![SubscribeEntry {
+        path: SPEED.to_string(),
+        view: View::CurrentValue.into(),
+        fields: vec![Field::Value.into()],
+    }]
+}
+
+async fn next_event(subscription: &mut ManagedSubscription) -> SubscriptionEvent {
+    tokio::time::timeout(Duration::from_secs(5), subscription.next_event())
+        .await
+        .expect("no event within 5s")
+        .expect("subscription ended")
+}
+
+fn speed(event: SubscriptionEvent) -> Option<Value> {
+    match event {
+        SubscriptionEvent::Update(response) => {
+            value_from_datapoint(response.updates[0].entry.clone()?.value)
+        }
+        other => panic!("expected an update, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn resubscribes_after_databroker_restart() {
+    let databroker = databroker();
+    let server = databroker.serve().unwrap();
+    let address = server.address();
+
+    let client = KuksaClient::new(&server.url()).with_backoff(quick_backoff());
+    let options = SubscriptionOptions::new().replay_latest(true);
+    let mut subscription = client.subscribe_managed(speed_entry(), options);
+
+    // the current value comes first, none yet
+    assert_eq!(speed(next_event(&mut subscription).await), None);
+    client.set_current_value(SPEED, "10").await.unwrap();
+    assert_eq!(
+        speed(next_event(&mut subscription).await),
+        Some(Value::Float(10.0))
+    );
+
+    server.stop();
+    assert!(matches!(
+        next_event(&mut subscription).await,
+        SubscriptionEvent::Interrupted(_)
+    ));
+
+    let _server = databroker.serve_on(address).unwrap();
+    assert!(matches!(
+        next_event(&mut subscription).await,
+        SubscriptionEvent::Resumed { .. }
+    ));
+    assert_eq!(
+        speed(next_event(&mut subscription).await),
+        Some(Value::Float(10.0))
+    );
+
+    // the new stream starts with the current value as well
+    client.set_current_value(SPEED, "20").await.unwrap();
+    let mut latest = speed(next_event(&mut subscription).await);
+    if latest == Some(Value::Float(10.0)) {
+        latest = speed(next_event(&mut subscription).await);
+    }
+    assert_eq!(latest, Some(Value::Float(20.0)));
+}
+
+#[tokio::test]
+async fn fails_for_an_unreadable_token_file() {
+    let server = databroker().serve().unwrap();
+    let client = KuksaClient::new(&server.url())
+        .with_token(TokenProvider::File("/nonexistent/token".into()));
+
+    // retried forever if the misconfiguration was taken for a lost connection
+    let options = SubscriptionOptions::new().backoff(quick_backoff());
+    let mut subscription = client.subscribe_managed(speed_entry(), options);
+
+    assert!(matches!(
+        next_event(&mut subscription).await,
+        SubscriptionEvent::Failed(ClientError::Config(_))
+    ));
+    assert!(subscription.next_event().await.is_none());
+}
+
+#[tokio::test]
+async fn fails_for_unknown_path() {
+    let server = databroker().serve().unwrap();
+    let client = KuksaClient::new(&server.url());
+
+    let entries = vec![SubscribeEntry {
+        path: "Vehicle.Unknown".to_string(),
+        view: View::CurrentValue.into(),
+        fields: vec![Field::Value.into()],
+    }];
+    let mut subscription = client.subscribe_managed(entries, SubscriptionOptions::new());
+
+    assert!(matches!(
+        next_event(&mut subscription).await,
+        SubscriptionEvent::Failed(_)
+    ));
+    assert!(subscription.next_event().await.is_none());
+}
@@ -0,0 +1,193 @@
+mod common;
+
+use std::time::{Duration, Instant};
+
+use databroker_proto::kuksa::val::v1::value_restriction::Type;
+use databroker_proto::kuksa::val::v1::{
+    DataEntry, DataType, Datapoint, EntryType, EntryUpdate, Field, SubscribeEntry, View,
+};
+use simple_kuksa_client::common::{ClientError, Value};
+use simple_kuksa_client::testing::{Method, MockDatabroker};
+use simple_kuksa_client::{SubscriptionEvent, SubscriptionOptions};
+use tokio_stream::StreamExt;
+use tonic::Status;
+
+use common::databroker;
+
+const SPEED: &str = "Vehicle.Speed";
+const IS_OPEN: &str = "Vehicle.Cabin.Door.Row1.Left.IsOpen";
+const HEATING: &str = "Vehicle.Cabin.Seat.Row1.Pos1.Heating";
+const POSITION: &str = "Vehicle.Cabin.Seat.Row1.Pos1.Position";
+
+#[tokio::test]
+async fn loads_the_vss_metadata() {
+    let server = databroker().serve().unwrap();
+    let client = server.client();
+
+    let metadata = client.get_metadata("Vehicle.Cabin.Seat").await.unwrap();
+    assert_eq!(metadata.len(), 2);
+    assert_eq!(metadata[POSITION].unit.as_deref(), Some("mm"));
+    assert_eq!(metadata[POSITION].entry_type, EntryType::Actuator as i32);
+    match metadata[HEATING]
+        .value_restriction
+        .as_ref()
+        .and_then(|restriction| restriction.r#type.as_ref())
+    {
+        Some(Type::Signed(restriction)) => {
+            assert_eq!(restriction.allowed_values, vec![-100, 0, 100])
+        }
+        other => panic!("expected a signed restriction, got {:?}", other),
+    }
+
+    let server_info = client.server_info().await.unwrap();
+    assert_eq!(server_info.name, "databroker");
+}
+
+#[tokio::test]
+async fn gets_sets_and_subscribes() {
+    let databroker = databroker().with_value(SPEED, Value::Float(30.0));
+    let server = databroker.serve().unwrap();
+    let client = server.client();
+
+    // the current values come first, then every change below the wildcard
+    let mut updates = client.subscribe_current_value("Vehicle.*").await.unwrap();
+    let initial = updates.next().await.unwrap().unwrap();
+    assert_eq!(initial.updates.len(), 8);
+
+    client.set_current_value(SPEED, "88.5").await.unwrap();
+    client.set_target_value(IS_OPEN, "true").await.unwrap();
+    assert_eq!(databroker.value(SPEED), Some(Value::Float(88.5)));
+    assert_eq!(databroker.target(IS_OPEN), Some(Value::Bool(true)));
+
+    let update = updates.next().await.unwrap().unwrap();
+    let entry = update.updates[0].entry.as_ref().unwrap();
+    assert_eq!(entry.path, SPEED);
+    assert_eq!(
+        entry.value.as_ref().unwrap().value,
+        Some(Value::Float(88.5))
+    );
+
+    // a feeder in the test
+    databroker.set_value(IS_OPEN, Value::Bool(true));
+    let datapoint = client.get_current_value(IS_OPEN).await.unwrap().unwrap();
+    assert_eq!(datapoint.value, Some(Value::Bool(true)));
+
+    assert!(matches!(
+        client.get_current_value("Vehicle.Unknown").await,
+        Err(ClientError::NotFound { .. })
+    ));
+}
+
+#[tokio::test]
+async fn checks_values_like_the_databroker() {
+    let databroker = databroker();
+    let server = databroker.serve().unwrap();
+    let client = server.client().with_restriction_check(false);
+
+    match client.set_target_value(HEATING, "50").await {
+        Err(ClientError::OutOfRange { path, .. }) => assert_eq!(path, HEATING),
+        other => panic!(
+            "expected the databroker to reject the value, got {:?}",
+            other
+        ),
+    }
+    assert_eq!(databroker.target(HEATING), None);
+
+    // feeders stream updates, only rejected batches are answered
+    let (updates, mut errors) = client.streamed_updater(4).await.unwrap();
+    let update = |path: &str, value: Value| EntryUpdate {
+        entry: Some(DataEntry {
+            path: path.to_string(),
+            value: Some(Datapoint {
+                timestamp: None,
+                value: Some(value),
+            }),
+            actuator_target: None,
+            metadata: None,
+        }),
+        fields: vec![Field::Value.into()],
+    };
+    updates
+        .send(vec![update(SPEED, Value::Float(12.0))])
+        .await
+        .unwrap();
+    updates
+        .send(vec![update(SPEED, Value::Bool(true))])
+        .await
+        .unwrap();
+
+    let rejected = errors.next().await.unwrap().unwrap();
+    assert_eq!(rejected[0].path, SPEED);
+    assert_eq!(rejected[0].error.as_ref().unwrap().reason, "type mismatch");
+    assert_eq!(databroker.value(SPEED), Some(Value::Float(12.0)));
+}
+
+#[tokio::test]
+async fn injects_errors_and_latency() {
+    let databroker = MockDatabroker::new().with_signal(SPEED, DataType::Float, EntryType::Sensor);
+    let server = databroker.serve().unwrap();
+    let client = server.client();
+    client.connect().await.unwrap();
+
+    // a lost connection is retried once by the client
+    databroker.fail_next(Method::Get, Status::unavailable("restarting"));
+    assert!(client.get_current_value(SPEED).await.is_ok());
+
+    databroker.fail_always(Method::Set, Status::permission_denied("read only"));
+    assert!(matches!(
+        client.set_current_value(SPEED, "1").await,
+        Err(ClientError::PermissionDenied { .. })
+    ));
+    assert!(client.set_current_value(SPEED, "1").await.is_err());
+    databroker.clear_failures();
+    client.set_current_value(SPEED, "1").await.unwrap();
+
+    databroker.set_latency(Duration::from_millis(100));
+    let start = Instant::now();
+    client.get_current_value(SPEED).await.unwrap();
+    assert!(start.elapsed() >= Duration::from_millis(100));
+}
+
+#[tokio::test]
+async fn interrupts_subscriptions() {
+    let databroker = MockDatabroker::new().with_signal(SPEED, DataType::Float, EntryType::Sensor);
+    let server = databroker.serve().unwrap();
+    let client = server.client();
+
+    let mut subscription = client.subscribe_managed(
+        vec![SubscribeEntry {
+            path: SPEED.to_string(),
+            view: View::CurrentValue.into(),
+            fields: vec![Field::Value.into()],
+        }],
+        SubscriptionOptions::new(),
+    );
+    assert!(matches!(
+        subscription.next().await,
+        Some(SubscriptionEvent::Update(_))
+    ));
+    assert_eq!(databroker.subscriber_count(), 1);
+
+    databroker.close_subscriptions(Status::unavailable("restarting"));
+    assert!(matches!(
+        subscription.next().await,
+        Some(SubscriptionEvent::Interrupted(_))
+    ));
+    assert!(matches!(
+        subscription.next().await,
+        Some(SubscriptionEvent::Resumed { .. })
+    ));
+
+    databroker.set_value(SPEED, Value::Float(5.0));
+    loop {
+        match subscription.next().await {
+            Some(SubscriptionEvent::Update(response)) => {
+                let entry = response.updates[0].entry.clone().unwrap();
+                if entry.value.and_then(|datapoint| datapoint.value) == Some(Value::Float(5.0)) {
+                    break;
+                }
+            }
+            other => panic!("expected an update, got {:?}", other),
+        }
+    }
+}
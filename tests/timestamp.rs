@@ -0,0 +1,144 @@
+use std::time::{Duration, UNIX_EPOCH};
+
+use databroker_proto::kuksa::val::v1::{DataType, EntryType, Int64Array};
+use simple_kuksa_client::common::{str_to_value, ClientError, Value};
+use simple_kuksa_client::testing::MockDatabroker;
+use simple_kuksa_client::timestamp::{
+    format_timestamp, nanos_to_system_time, parse_timestamp, system_time_to_nanos,
+    timestamp_to_string, timestamp_to_system_time,
+};
+use simple_kuksa_client::KuksaClient;
+
+const SPEED: &str = "Vehicle.Speed";
+const LAST_SERVICE: &str = "Vehicle.Service.LastServiceDate";
+
+// 2024-05-01T12:00:00Z
+const MAY_FIRST: u64 = 1_714_564_800;
+
+#[test]
+fn parses_rfc3339_and_epoch_millis() {
+    let expected = UNIX_EPOCH + Duration::from_millis(MAY_FIRST * 1000 + 500);
+
+    assert_eq!(parse_timestamp("2024-05-01T12:00:00.5Z").unwrap(), expected);
+    assert_eq!(
+        parse_timestamp("2024-05-01T14:00:00.500+02:00").unwrap(),
+        expected
+    );
+    assert_eq!(parse_timestamp("1714564800500").unwrap(), expected);
+    assert!(matches!(
+        parse_timestamp("yesterday"),
+        Err(ClientError::Parse(_))
+    ));
+
+    assert_eq!(format_timestamp(expected), "2024-05-01T12:00:00.5Z");
+}
+
+#[test]
+fn converts_prost_timestamps() {
+    let timestamp = prost_types::Timestamp {
+        seconds: MAY_FIRST as i64,
+        nanos: 0,
+    };
+
+    assert_eq!(
+        timestamp_to_system_time(&timestamp).unwrap(),
+        UNIX_EPOCH + Duration::from_secs(MAY_FIRST)
+    );
+    assert_eq!(timestamp_to_string(&timestamp), "2024-05-01T12:00:00Z");
+
+    let before_epoch = UNIX_EPOCH - Duration::from_millis(1500);
+    assert_eq!(system_time_to_nanos(before_epoch), -1_500_000_000);
+    assert_eq!(nanos_to_system_time(-1_500_000_000), before_epoch);
+}
+
+#[test]
+fn str_to_value_parses_timestamps() {
+    let nanos = MAY_FIRST as i64 * 1_000_000_000;
+
+    assert_eq!(
+        str_to_value("2024-05-01T12:00:00Z", DataType::Timestamp).unwrap(),
+        Value::Int64(nanos)
+    );
+    assert_eq!(
+        str_to_value(
+            r#"["2024-05-01T12:00:00Z", 1714564800000]"#,
+            DataType::TimestampArray
+        )
+        .unwrap(),
+        Value::Int64Array(Int64Array {
+            values: vec![nanos, nanos]
+        })
+    );
+    assert!(str_to_value("[noon]", DataType::TimestampArray).is_err());
+}
+
+#[tokio::test]
+async fn sets_values_with_a_source_timestamp() {
+    let server = MockDatabroker::new()
+        .with_signal(SPEED, DataType::Float, EntryType::Sensor)
+        .with_signal(LAST_SERVICE, DataType::Timestamp, EntryType::Attribute)
+        .serve()
+        .unwrap();
+    let client = KuksaClient::new(&server.url());
+    let measured = UNIX_EPOCH + Duration::from_secs(MAY_FIRST);
+
+    client
+        .set_current_value_at(SPEED, "42", measured)
+        .await
+        .unwrap();
+    client
+        .set_current_value(LAST_SERVICE, "2024-05-01T12:00:00Z")
+        .await
+        .unwrap();
+
+    let speed = client.get_current_value(SPEED).await.unwrap().unwrap();
+    assert_eq!(
+        timestamp_to_system_time(speed.timestamp.as_ref().unwrap()).unwrap(),
+        measured
+    );
+
+    let last_service = client
+        .get_current_value(LAST_SERVICE)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        last_service.value,
+        Some(Value::Int64(system_time_to_nanos(measured)))
+    );
+}
+
+#[tokio::test]
+async fn sets_batches_with_a_source_timestamp() {
+    let databroker = MockDatabroker::new()
+        .with_signal(SPEED, DataType::Float, EntryType::Sensor)
+        .with_signal(LAST_SERVICE, DataType::Timestamp, EntryType::Actuator);
+    let server = databroker.serve().unwrap();
+    let client = KuksaClient::new(&server.url());
+    let requested = UNIX_EPOCH + Duration::from_secs(MAY_FIRST);
+
+    // Timestamp signals are Int64 nanoseconds
+    let last_service = Value::Int64(system_time_to_nanos(requested));
+    client
+        .batch_set_target_values_at(vec![(LAST_SERVICE, last_service.clone())], requested)
+        .await
+        .unwrap();
+    assert_eq!(databroker.target(LAST_SERVICE), Some(last_service));
+
+    let datapoint = client
+        .get_target_value(LAST_SERVICE)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(
+        timestamp_to_system_time(datapoint.timestamp.as_ref().unwrap()).unwrap(),
+        requested
+    );
+
+    assert!(matches!(
+        client
+            .batch_set_target_values_at(vec![(SPEED, Value::Float(1.0))], requested)
+            .await,
+        Err(ClientError::Entries(_))
+    ));
+}
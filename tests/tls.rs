@@ -0,0 +1,123 @@
+mod common;
+
+use rcgen::{BasicConstraints, Certificate, CertificateParams, IsCa};
+use tonic::transport::{Identity, ServerTlsConfig};
+
+use simple_kuksa_client::common::{value_from_datapoint, ClientError, Value};
+use simple_kuksa_client::{BackoffPolicy, KuksaClient, TlsConfig};
+
+use common::databroker;
+
+const SPEED: &str = "Vehicle.Speed";
+
+struct Pki {
+    ca: Certificate,
+}
+
+impl Pki {
+    fn new() -> Self {
+        let mut params = CertificateParams::new(vec![]);
+        params.is_ca = IsCa::Ca(BasicConstraints::Unconstrained);
+        Pki {
+            ca: Certificate::from_params(params).unwrap(),
+        }
+    }
+
+    fn ca_pem(&self) -> String {
+        self.ca.serialize_pem().unwrap()
+    }
+
+    // returns (certificate, private key) signed by the CA
+    fn issue(&self, name: &str) -> (String, String) {
+        let cert =
+            Certificate::from_params(CertificateParams::new(vec![name.to_string()])).unwrap();
+        (
+            cert.serialize_pem_with_signer(&self.ca).unwrap(),
+            cert.serialize_private_key_pem(),
+        )
+    }
+}
+
+#[tokio::test]
+async fn connects_with_ca_certificate_and_domain_override() {
+    let pki = Pki::new();
+    let (cert, key) = pki.issue("databroker.test");
+    let server_tls = ServerTlsConfig::new().identity(Identity::from_pem(cert, key));
+    let server = databroker().serve_tls(server_tls).unwrap();
+
+    let tls = TlsConfig::new()
+        .ca_certificate(pki.ca_pem())
+        .domain_name("databroker.test");
+    let client = KuksaClient::new(&server.url()).with_tls(tls);
+    client.connect().await.unwrap();
+
+    client.set_current_value(SPEED, "42.5").await.unwrap();
+    let datapoint = client.get_current_value(SPEED).await.unwrap();
+    assert_eq!(value_from_datapoint(datapoint), Some(Value::Float(42.5)));
+}
+
+#[tokio::test]
+async fn connects_with_client_certificate() {
+    let pki = Pki::new();
+    let (cert, key) = pki.issue("databroker.test");
+    let server_tls = ServerTlsConfig::new()
+        .identity(Identity::from_pem(cert, key))
+        .client_ca_root(tonic::transport::Certificate::from_pem(pki.ca_pem()));
+    let server = databroker().serve_tls(server_tls).unwrap();
+
+    let (client_cert, client_key) = pki.issue("vehicle-app");
+    let tls = TlsConfig::new()
+        .ca_certificate(pki.ca_pem())
+        .identity(client_cert, client_key)
+        .domain_name("databroker.test");
+    let client = KuksaClient::new(&format!("https://{}", server.address())).with_tls(tls);
+    client.connect().await.unwrap();
+
+    assert!(client.get_current_value(SPEED).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn rejects_untrusted_server_certificate() {
+    let pki = Pki::new();
+    let (cert, key) = pki.issue("databroker.test");
+    let server_tls = ServerTlsConfig::new().identity(Identity::from_pem(cert, key));
+    let server = databroker().serve_tls(server_tls).unwrap();
+
+    let other_ca = Pki::new();
+    let tls = TlsConfig::new()
+        .ca_certificate(other_ca.ca_pem())
+        .domain_name("databroker.test");
+    let client = KuksaClient::new(&server.url())
+        .with_tls(tls)
+        .with_backoff(BackoffPolicy::new().max_retries(Some(0)));
+
+    assert!(matches!(
+        client.connect().await,
+        Err(ClientError::Connection(_))
+    ));
+}
+
+#[tokio::test]
+async fn reads_certificates_from_files() {
+    let pki = Pki::new();
+    let (cert, key) = pki.issue("databroker.test");
+    let server_tls = ServerTlsConfig::new().identity(Identity::from_pem(cert, key));
+    let server = databroker().serve_tls(server_tls).unwrap();
+
+    let ca_path = std::env::temp_dir().join(format!("kuksa-ca-{}.pem", server.address().port()));
+    std::fs::write(&ca_path, pki.ca_pem()).unwrap();
+
+    let tls = TlsConfig::new()
+        .ca_certificate_file(&ca_path)
+        .unwrap()
+        .domain_name("databroker.test");
+    let client = KuksaClient::new(&server.url()).with_tls(tls);
+    let result = client.connect().await;
+    std::fs::remove_file(&ca_path).unwrap();
+    result.unwrap();
+
+    assert!(matches!(
+        TlsConfig::new().ca_certificate_file("/nonexistent/ca.pem"),
+        Err(ClientError::Config(_))
+    ));
+}
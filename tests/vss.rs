@@ -0,0 +1,76 @@
+// generated with: kuksa-cli codegen tests/data/vss.json -o tests/data/vss.rs
+#[rustfmt::skip]
+#[path = "data/vss.rs"]
+mod vss;
+
+mod common;
+
+use databroker_proto::kuksa::val::v1::{DataType, EntryType};
+use simple_kuksa_client::common::ClientError;
+use simple_kuksa_client::KuksaClient;
+
+use vss::vehicle;
+
+#[test]
+fn generated_code_is_up_to_date() {
+    let code = simple_kuksa_client::vss::generate(include_str!("data/vss.json")).unwrap();
+    assert_eq!(code, include_str!("data/vss.rs"));
+}
+
+#[test]
+fn describes_the_leaves() {
+    let position = vehicle::cabin::seat::row1::pos1::Position;
+    assert_eq!(position.path, "Vehicle.Cabin.Seat.Row1.Pos1.Position");
+    assert_eq!(position.data_type, DataType::Uint16);
+    assert_eq!(position.entry_type, EntryType::Actuator);
+    assert_eq!(position.unit, Some("mm"));
+
+    let mode = vehicle::powertrain::transmission::PerformanceMode;
+    assert_eq!(mode.allowed, ["NORMAL", "SPORT", "ECONOMY", "SNOW", "RAIN"]);
+    assert_eq!(
+        vehicle::cabin::seat::row1::pos1::Heating.allowed,
+        ["-100", "0", "100"]
+    );
+
+    assert_eq!(
+        vehicle::vehicle_identification::VIN.entry_type,
+        EntryType::Attribute
+    );
+    assert_eq!(vehicle::obd::DTCList.data_type, DataType::StringArray);
+}
+
+#[test]
+fn rejects_invalid_specs() {
+    for spec in [
+        "not json",
+        "[]",
+        r#"{"Vehicle": {"type": "branch", "children": {"Speed": {"type": "sensor"}}}}"#,
+    ] {
+        match simple_kuksa_client::vss::generate(spec) {
+            Err(ClientError::Parse(message)) => assert!(message.starts_with("Invalid VSS JSON")),
+            other => panic!("expected a parse error for {:?}, got {:?}", spec, other),
+        }
+    }
+}
+
+#[tokio::test]
+async fn works_with_kuksa_client() {
+    let position = vehicle::cabin::seat::row1::pos1::Position;
+    let server = common::databroker().serve().unwrap();
+    let client = KuksaClient::new(&server.url());
+
+    client.set_target_value(position.path, "300").await.unwrap();
+
+    let seat = position.signal(&client).await.unwrap();
+    assert_eq!(seat.get_target().await.unwrap(), Some(300u16));
+    seat.set_target(250).await.unwrap();
+    assert_eq!(seat.get_target().await.unwrap(), Some(250));
+
+    let speed = vehicle::Speed.signal(&client).await.unwrap();
+    speed.set(50.0).await.unwrap();
+    assert!(client
+        .get_current_value(vehicle::Speed.path)
+        .await
+        .unwrap()
+        .is_some());
+}